@@ -0,0 +1,305 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use crate::eq::Equation;
+use crate::nerdle::{NerdleError, NERDLE_CHARACTERS};
+use crate::rules::RuleSet;
+
+// Curated answer lists, like test.in: one equation per line, with blank lines and # comments ignored.
+// Each entry can be tagged with its pattern and a rough difficulty, so commands can pick out subsets.
+
+const OPERATORS: &str = "+-*/";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(NerdleError { message: format!("Unrecognized difficulty '{}'", input)})
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+// What an entry looks like, for picking out subsets of a list
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnswerTags {
+    // Digits replaced by N, e.g. "NN+NN=NN"
+    pub pattern: String,
+    pub ops: usize,
+    pub difficulty: Difficulty,
+}
+
+impl AnswerTags {
+    pub fn new(eq: &Equation) -> AnswerTags {
+        let eq = eq.to_string();
+        let pattern: String = eq.chars().map(|ch| if ch.is_ascii_digit() { 'N' } else { ch }).collect();
+        let ops = eq.chars().filter(|ch| OPERATORS.contains(*ch)).count();
+        AnswerTags {
+            difficulty: AnswerTags::difficulty(&eq, ops),
+            pattern,
+            ops,
+        }
+    }
+
+    // A rough guess from the shape of the equation.  More operators, multiplication and division, repeated
+    // digits and short results all make an answer harder to find.
+    fn difficulty(eq: &str, ops: usize) -> Difficulty {
+        // An entry with no operator at all isn't a valid answer, but it can still be tagged
+        let mut score = ops.saturating_sub(1);
+        if eq.contains('*') || eq.contains('/') {
+            score += 1;
+        }
+        let digits: Vec<char> = eq.chars().filter(|ch| ch.is_ascii_digit()).collect();
+        let unique: HashSet<&char> = digits.iter().collect();
+        score += digits.len() - unique.len();
+        let result_digits = eq.split('=').nth(1).map(|res| res.len()).unwrap_or(0);
+        if result_digits == 1 {
+            score += 1;
+        }
+        match score {
+            0..=1 => Difficulty::Easy,
+            2..=3 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+impl fmt::Display for AnswerTags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "pattern={} ops={} difficulty={}", self.pattern, self.ops, self.difficulty)
+    }
+}
+
+// Which entries to keep, e.g. "ops=2,difficulty=hard" or "pattern=NNONN=NN", where O matches any operator.
+// Entries must match every kind of condition given.  A kind given more than once, like "ops=1,ops=2", matches if
+// any of its values do.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnswerFilter {
+    patterns: Vec<String>,
+    ops: Vec<usize>,
+    difficulties: Vec<Difficulty>,
+}
+
+impl AnswerFilter {
+    pub fn matches(&self, tags: &AnswerTags) -> bool {
+        (self.patterns.is_empty() || self.patterns.iter().any(|pattern| AnswerFilter::pattern_matches(pattern, &tags.pattern)))
+            && (self.ops.is_empty() || self.ops.contains(&tags.ops))
+            && (self.difficulties.is_empty() || self.difficulties.contains(&tags.difficulty))
+    }
+
+    fn pattern_matches(filter: &str, pattern: &str) -> bool {
+        filter.len() == pattern.len() && filter.chars().zip(pattern.chars())
+            .all(|(want, have)| want == have || (want == 'O' && OPERATORS.contains(have)))
+    }
+}
+
+impl FromStr for AnswerFilter {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut filter = AnswerFilter::default();
+        for condition in input.split(',').map(|condition| condition.trim()).filter(|condition| !condition.is_empty()) {
+            match condition.split_once('=') {
+                Some(("pattern", pattern)) => filter.patterns.push(pattern.to_string()),
+                Some(("ops", ops)) => filter.ops.push(usize::from_str(ops)
+                    .map_err(|err| NerdleError { message: format!("Invalid operator count '{}': {}", ops, err)})?),
+                Some(("difficulty", difficulty)) => filter.difficulties.push(Difficulty::from_str(difficulty)?),
+                _ => return Err(NerdleError { message: format!("Unrecognized tag condition '{}'", condition)})
+            }
+        }
+        Ok(filter)
+    }
+}
+
+pub struct AnswerList {
+    pub answers: Vec<Equation>,
+    // What validate() checks the entries against
+    pub rules: RuleSet,
+}
+
+impl AnswerList {
+    pub fn new(answers: Vec<Equation>) -> AnswerList {
+        AnswerList {
+            answers,
+            rules: RuleSet::default(),
+        }
+    }
+
+    pub fn load(file_name: &str) -> Result<AnswerList, NerdleError> {
+        let mut input = String::new();
+        File::open(file_name)
+            .and_then(|mut file| file.read_to_string(&mut input))
+            .map_err(|err| NerdleError { message: format!("Error reading file '{}': {}", file_name, err)})?;
+        AnswerList::parse(&input, file_name)
+    }
+
+    // One equation per line, skipping blank lines and # comments.  The source is only for error messages.
+    pub fn parse(input: &str, source: &str) -> Result<AnswerList, NerdleError> {
+        let mut answers = Vec::new();
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            answers.push(Equation::from_str(line)
+                .map_err(|err| NerdleError { message: format!("Invalid equation '{}' in '{}': {}", line, source, err)})?);
+        }
+        Ok(AnswerList::new(answers))
+    }
+
+    pub fn save(&self, file_name: &str) -> Result<(), NerdleError> {
+        let mut file = File::create(file_name)
+            .map_err(|err| NerdleError { message: format!("Error creating file '{}': {}", file_name, err)})?;
+        for answer in self.answers.iter() {
+            writeln!(file, "{}", answer)
+                .map_err(|err| NerdleError { message: format!("Error writing file '{}': {}", file_name, err)})?;
+        }
+        Ok(())
+    }
+
+    // Why each entry that couldn't be a real answer is wrong
+    pub fn validate(&self) -> Vec<NerdleError> {
+        self.answers.iter().filter_map(|answer| AnswerList::check(&self.rules, answer)).collect()
+    }
+
+    // Drop the entries validate() complains about, returning the complaints
+    pub fn remove_invalid(&mut self) -> Vec<NerdleError> {
+        let mut errors = Vec::new();
+        let rules = self.rules;
+        self.answers.retain(|answer| match AnswerList::check(&rules, answer) {
+            Some(err) => {
+                errors.push(err);
+                false
+            },
+            None => true,
+        });
+        errors
+    }
+
+    fn check(rules: &RuleSet, answer: &Equation) -> Option<NerdleError> {
+        match answer.len() {
+            Err(err) => Some(NerdleError { message: format!("Equation '{}' has invalid length: {}", answer, err)}),
+            Ok(len) if len != NERDLE_CHARACTERS as usize =>
+                Some(NerdleError { message: format!("Equation '{}' is wrong length ({} chars != {})", answer, len, NERDLE_CHARACTERS)}),
+            Ok(_) => rules.check(answer).err(),
+        }
+    }
+
+    // Keep only the first of each set of entries which are commuted forms of each other (and exact repeats)
+    pub fn dedupe_commutative(&mut self) -> Vec<Equation> {
+        let mut seen = HashSet::new();
+        let mut removed = Vec::new();
+        self.answers.retain(|answer| {
+            if seen.insert(answer.canonical()) {
+                true
+            } else {
+                removed.push(answer.clone());
+                false
+            }
+        });
+        removed
+    }
+
+    pub fn tagged(&self) -> Vec<(&Equation, AnswerTags)> {
+        self.answers.iter().map(|answer| (answer, AnswerTags::new(answer))).collect()
+    }
+
+    pub fn filter(&self, filter: &AnswerFilter) -> AnswerList {
+        AnswerList::new(self.tagged().into_iter()
+            .filter(|(_, tags)| filter.matches(tags))
+            .map(|(answer, _)| answer.clone())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+fn answer_test_list() -> AnswerList {
+    AnswerList::new(["12+34=46", "34+12=46", "9*12=108", "12*9=108", "4+3+3=10", "1+2=3", "10+20=31"]
+        .iter().map(|eq| Equation::from_str(eq).unwrap()).collect())
+}
+
+#[test]
+fn answer_tags_test() {
+    let tags = AnswerTags::new(&Equation::from_str("12+34=46").unwrap());
+    assert_eq!(tags.pattern, "NN+NN=NN");
+    assert_eq!(tags.ops, 1);
+    assert_eq!(tags.difficulty, Difficulty::Easy);
+    assert_eq!(tags.to_string(), "pattern=NN+NN=NN ops=1 difficulty=easy");
+
+    assert_eq!(AnswerTags::new(&Equation::from_str("9*9-2=79").unwrap()).difficulty, Difficulty::Hard);
+
+    let tags = AnswerTags::new(&Equation::from_str("12345=12345").unwrap());
+    assert_eq!(tags.ops, 0);
+    assert_eq!(tags.difficulty, Difficulty::Hard);
+}
+
+#[test]
+fn answer_list_validate_test() {
+    let list = answer_test_list();
+    let errors: Vec<String> = list.validate().iter().map(|err| err.message.clone()).collect();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("1+2=3"));
+    assert!(errors[1].contains("10+20=31"));
+
+    let mut list = list;
+    assert_eq!(list.remove_invalid().len(), 2);
+    assert!(list.validate().is_empty());
+    assert_eq!(list.answers.len(), 5);
+}
+
+#[test]
+fn answer_list_dedupe_test() {
+    let mut list = answer_test_list();
+    let removed: Vec<String> = list.dedupe_commutative().iter().map(|eq| eq.to_string()).collect();
+    assert_eq!(removed, vec!["34+12=46", "12*9=108"]);
+    assert_eq!(list.answers.len(), 5);
+}
+
+#[test]
+fn answer_filter_test() {
+    let list = answer_test_list();
+    let filtered = |filter: &str| -> Vec<String> {
+        list.filter(&AnswerFilter::from_str(filter).unwrap()).answers.iter().map(|eq| eq.to_string()).collect()
+    };
+    assert_eq!(filtered("ops=2"), vec!["4+3+3=10"]);
+    assert_eq!(filtered("pattern=NNONN=NN,pattern=NNONN=NNN"), vec!["12+34=46", "34+12=46", "10+20=31"]);
+    assert_eq!(filtered("pattern=NNONN=NN,difficulty=easy"), vec!["12+34=46", "34+12=46"]);
+    assert_eq!(filtered("").len(), list.answers.len());
+    assert!(AnswerFilter::from_str("colour=red").is_err());
+    assert!(AnswerFilter::from_str("difficulty=brutal").is_err());
+}
+
+#[test]
+fn answer_list_save_load_test() {
+    let file_name = std::env::temp_dir().join(format!("nerdlebot-answers-{}.txt", std::process::id()));
+    let file_name = file_name.to_str().unwrap();
+    let list = answer_test_list();
+    list.save(file_name).unwrap();
+    let loaded = AnswerList::load(file_name).unwrap();
+    std::fs::remove_file(file_name).unwrap();
+    assert_eq!(loaded.answers.iter().map(|eq| eq.to_string()).collect::<Vec<String>>(),
+               list.answers.iter().map(|eq| eq.to_string()).collect::<Vec<String>>());
+}
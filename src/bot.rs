@@ -0,0 +1,196 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use crate::eq::Equation;
+use crate::host::GameMode;
+use crate::nerdle::{NerdleResult, NerdleError};
+use crate::strategy::{Strategy, StrategyEnum};
+
+// A line-oriented protocol for driving a strategy from another program.  The harness sends one command per
+// line and the bot answers each with exactly one line:
+//
+//   NEWGAME [mode]           -> OK                  Start a new game; mode is classic (default), commutative or adversarial
+//   GUESS?                   -> GUESS <equation>    Ask the bot for its next guess
+//   RESULT <result> [guess]  -> OK or WON           Result of the last guess, or of the given guess
+//   QUIT                     -> OK                  The bot exits
+//
+// Anything that goes wrong is answered with ERROR <message>, and the bot carries on.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BotCommand {
+    NewGame(GameMode),
+    Guess,
+    Result(NerdleResult, Option<String>),
+    Quit,
+}
+
+impl FromStr for BotCommand {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        match words.as_slice() {
+            ["NEWGAME"] => Ok(BotCommand::NewGame(GameMode::Classic)),
+            ["NEWGAME", mode] => Ok(BotCommand::NewGame(GameMode::from_str(mode)?)),
+            ["GUESS?"] => Ok(BotCommand::Guess),
+            ["RESULT", res] => Ok(BotCommand::Result(NerdleResult::from_str(res)?, None)),
+            ["RESULT", res, guess] => Ok(BotCommand::Result(NerdleResult::from_str(res)?, Some(guess.to_string()))),
+            ["QUIT"] => Ok(BotCommand::Quit),
+            _ => Err(NerdleError { message: format!("Unrecognized command '{}'", input.trim())})
+        }
+    }
+}
+
+impl fmt::Display for BotCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BotCommand::NewGame(mode) => write!(f, "NEWGAME {}", mode),
+            BotCommand::Guess => write!(f, "GUESS?"),
+            BotCommand::Result(res, None) => write!(f, "RESULT {}", res),
+            BotCommand::Result(res, Some(guess)) => write!(f, "RESULT {} {}", res, guess),
+            BotCommand::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BotResponse {
+    Ok,
+    Guess(String),
+    Won,
+    Error(String),
+}
+
+impl FromStr for BotResponse {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let (word, rest) = input.split_once(' ').unwrap_or((input, ""));
+        match (word, rest.trim()) {
+            ("OK", "") => Ok(BotResponse::Ok),
+            ("WON", "") => Ok(BotResponse::Won),
+            ("GUESS", guess) if !guess.is_empty() => Ok(BotResponse::Guess(guess.to_string())),
+            ("ERROR", message) => Ok(BotResponse::Error(message.to_string())),
+            _ => Err(NerdleError { message: format!("Unrecognized response '{}'", input)})
+        }
+    }
+}
+
+impl fmt::Display for BotResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BotResponse::Ok => write!(f, "OK"),
+            BotResponse::Guess(guess) => write!(f, "GUESS {}", guess),
+            BotResponse::Won => write!(f, "WON"),
+            BotResponse::Error(message) => write!(f, "ERROR {}", message),
+        }
+    }
+}
+
+// The bot side of the protocol, playing with a named strategy
+pub struct Bot {
+    strategy_name: String,
+    strategy: StrategyEnum,
+    last_guess: Option<Equation>,
+}
+
+impl Bot {
+    pub fn new(strategy_name: &str) -> Result<Bot, NerdleError> {
+        Ok(Bot {
+            strategy_name: strategy_name.to_string(),
+            strategy: Bot::strategy(strategy_name)?,
+            last_guess: None,
+        })
+    }
+
+    fn strategy(name: &str) -> Result<StrategyEnum, NerdleError> {
+        StrategyEnum::by_name(name)
+            .map_err(|err| NerdleError { message: format!("{}", err)})
+    }
+
+    pub fn handle(&mut self, command: &BotCommand) -> Result<BotResponse, NerdleError> {
+        match command {
+            BotCommand::NewGame(mode) => {
+                self.strategy = Bot::strategy(&self.strategy_name)?;
+                self.strategy.set_commutative(*mode == GameMode::Commutative);
+                self.last_guess = None;
+                Ok(BotResponse::Ok)
+            },
+            BotCommand::Guess => {
+                let guess = self.strategy.take_guess()
+                    .map_err(|err| NerdleError { message: format!("No guess found: {}", err)})?;
+                let response = BotResponse::Guess(guess.to_string());
+                self.last_guess = Some(guess);
+                Ok(response)
+            },
+            BotCommand::Result(res, guess) => {
+                let guess = match guess {
+                    Some(guess) => Equation::from_str(guess)
+                        .map_err(|err| NerdleError { message: format!("Invalid guess '{}': {}", guess, err)})?,
+                    None => self.last_guess.take()
+                        .ok_or_else(|| NerdleError { message: "RESULT without a guess".to_string()})?,
+                };
+                if res.won() {
+                    return Ok(BotResponse::Won);
+                }
+                self.strategy.update(&guess, res);
+                Ok(BotResponse::Ok)
+            },
+            BotCommand::Quit => Ok(BotResponse::Ok),
+        }
+    }
+
+    // Answer commands until QUIT or the input ends
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = BotCommand::from_str(&line)
+                .and_then(|command| self.handle(&command).map(|response| (command, response)));
+            let (quit, response) = match response {
+                Ok((command, response)) => (command == BotCommand::Quit, response),
+                Err(err) => (false, BotResponse::Error(err.message)),
+            };
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+            if quit {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn bot_command_test() {
+    assert_eq!(BotCommand::from_str("NEWGAME").unwrap(), BotCommand::NewGame(GameMode::Classic));
+    assert_eq!(BotCommand::from_str("NEWGAME commutative").unwrap(), BotCommand::NewGame(GameMode::Commutative));
+    assert_eq!(BotCommand::from_str("GUESS?").unwrap(), BotCommand::Guess);
+    assert_eq!(BotCommand::from_str("RESULT G-GY-GY- 10+20=30").unwrap().to_string(), "RESULT G-GY-GY- 10+20=30");
+    assert!(BotCommand::from_str("RESULT GGZ").is_err());
+    assert!(BotCommand::from_str("HELLO").is_err());
+    assert_eq!(BotResponse::from_str("GUESS 10+20=30").unwrap(), BotResponse::Guess("10+20=30".to_string()));
+    assert_eq!(BotResponse::from_str("ERROR no idea").unwrap().to_string(), "ERROR no idea");
+    assert!(BotResponse::from_str("GUESS").is_err());
+}
+
+#[test]
+fn bot_run_test() {
+    let input = "NEWGAME\nGUESS?\nRESULT GGGGGGGG\nRESULT GGGGGGGG\nBOGUS\nQUIT\nGUESS?\n";
+    let mut output = Vec::new();
+    Bot::new("first_possible").unwrap().run(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "OK");
+    assert!(Equation::from_str(lines[1].strip_prefix("GUESS ").unwrap()).unwrap().computes().unwrap());
+    assert_eq!(lines[2], "WON");
+    // The guess was used up by the first result
+    assert!(lines[3].starts_with("ERROR"));
+    assert!(lines[4].starts_with("ERROR"));
+    assert_eq!(lines[5], "OK");
+}
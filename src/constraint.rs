@@ -1,50 +1,61 @@
 use std::fmt;
-use std::rc::Rc;
+use std::str::FromStr;
 use std::cmp::{min, max};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::RangeInclusive;
 
-use crate::nerdle::{NERDLE_NUM_MAX, NERDLE_MAX_OPS};
-use crate::expr::{ExpressionNumber, ExpressionOperator, ExpressionPart, mknum};
+use regex::Regex;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error;
+
+use crate::nerdle::{NerdleError, NERDLE_NUM_MAX, NERDLE_CHARACTERS, NERDLE_VALID_CHAR_BYTES};
+use crate::expr::{ExpressionNumber, ExpressionOperator, ExpressionPart};
 use crate::eq::{Equation};
-use crate::util::range_rand_or_only;
+use crate::eqgen::eqgen_search;
+use crate::rules::RuleSet;
+
+const DEFAULT_RANGE: RangeInclusive<i64> = 0..=NERDLE_NUM_MAX;
+
+// A regex which a number's digits must match.  Kept as its source text when serialized, so constraints stay
+// plain data.
+#[derive(Clone, Debug)]
+pub struct DigitPattern(pub Regex);
+
+impl DigitPattern {
+    pub fn is_match(&self, num: &ExpressionNumber) -> bool {
+        self.0.is_match(&num.to_string())
+    }
+}
 
-const ATTEMPTS: u32 = 1000;
-const DEFAULT_RANGE: RangeInclusive<i32> = 0..=NERDLE_NUM_MAX;
+impl Serialize for DigitPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
 
-thread_local! {
-    // Must be thread_local because Rc is not threadsafe
-    static ACCEPT_ANY_EXPRESSION_NUMBER_RC: Rc<dyn Fn(&ExpressionNumber) -> bool> = Rc::new(|_| true);
-    static ACCEPT_ANY_EQUATION_RC: Rc<dyn Fn(&Equation) -> bool> = Rc::new(|_| true);
+impl<'de> Deserialize<'de> for DigitPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        Regex::new(&source).map(DigitPattern).map_err(D::Error::custom)
+    }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ExpressionNumberConstraint
 {
-    pub range: RangeInclusive<i32>,
+    pub range: RangeInclusive<i64>,
     pub description: String,
-    pub accept: Rc<dyn Fn(&ExpressionNumber) -> bool>,
+    pub pattern: Option<DigitPattern>,
 }
 
 impl ExpressionNumberConstraint {
-    pub fn intersect(a: &ExpressionNumberConstraint, b: &ExpressionNumberConstraint) -> ExpressionNumberConstraint {
-        let a_accept = a.accept.clone();
-        let b_accept = b.accept.clone();
-        ExpressionNumberConstraint {
-            range: range_intersect(&a.range, &b.range),
-            description: format!("{} & {}", &a.description, &b.description),
-            accept: Rc::new(move |n| {
-                (a_accept)(n) && (b_accept)(n)
-            }),
-        }
-    }
-
     pub fn accept(&self, num: &ExpressionNumber) -> Result<(), NoMatchFound> {
         match num.int_value() {
             Ok(value) => {
                 if !self.range.contains(&value) {
                     Err(NoMatchFound { message: format!("Value {} is not in range: {}", num, self)})
-                } else if !(self.accept)(&num) {
-                    Err(NoMatchFound { message: format!("Value {} did not match accept function: {}", num, self)})
+                } else if !self.pattern.as_ref().map(|pattern| pattern.is_match(num)).unwrap_or(true) {
+                    Err(NoMatchFound { message: format!("Value {} did not match pattern: {}", num, self)})
                 } else {
                     Ok(())
                 }
@@ -56,11 +67,11 @@ impl ExpressionNumberConstraint {
 
 impl Default for ExpressionNumberConstraint {
     fn default() -> Self {
-        ACCEPT_ANY_EXPRESSION_NUMBER_RC.with(|accept_anything| Self {
+        Self {
             range: DEFAULT_RANGE.clone(),
             description: format!("Default range: {}..{}", DEFAULT_RANGE.start(), DEFAULT_RANGE.end()),
-            accept: accept_anything.clone(),
-        })
+            pattern: None,
+        }
     }
 }
 
@@ -71,88 +82,146 @@ impl fmt::Display for ExpressionNumberConstraint
     }
 }
 
-pub fn find_num_with_constraint(constraint: &ExpressionNumberConstraint) -> Result<ExpressionNumber, NoMatchFound>
-{
-    let constraint_range_size = (constraint.range.end() - constraint.range.start() + 1) as u32;
-    for _attempt in 0..min(constraint_range_size, ATTEMPTS) {
-        let candidate = match range_rand_or_only(constraint.range.clone()) {
-            Ok(num) => num,
-            Err(err) => return Err(NoMatchFound { message: format!("Could not find possibility for constraint {}: {}", constraint, err)}),
-        };
-        let candidate = mknum(candidate);
-        if !(constraint.accept)(&candidate) {
-            // println!("  Rejected {} with constraint {}", candidate, constraint);
-            continue;
-        }
-        // println!("Found num {} in {} tries for constraint {}", candidate, attempt, constraint);
-
-        return Ok(candidate);
-    }
-    // println!("Could not fund num in {} tries for constraint {}", ATTEMPTS, constraint);
-    Err(NoMatchFound { message: format!("No match found for constraint {} after {} tries", constraint, ATTEMPTS)})
-}
-
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EquationConstraint
 {
-    pub accept: Rc<dyn Fn(&Equation) -> bool>,
-    pub a_constraint: ExpressionNumberConstraint,
-    pub b_constraint: ExpressionNumberConstraint,
-    pub b2_constraint: ExpressionNumberConstraint,
+    // Constraints on the operands in order.  Operands past the end of the list can be anything.
+    pub operands: Vec<ExpressionNumberConstraint>,
     pub c_constraint: ExpressionNumberConstraint,
     pub operator: HashMap<u8, RangeInclusive<u32>>,
     pub num_ops: RangeInclusive<u32>,
+    // Characters allowed at each position, and how many of each character there can be.  These let the generator
+    // rule out whole branches before building an equation.  There is one position for each character, so this
+    // also sets how long equations are.
+    pub positions: Vec<HashSet<u8>>,
+    pub counts: HashMap<u8, RangeInclusive<u32>>,
+    // Canonical forms of equations which can't be the answer, for games where commuted forms win
+    pub excluded_canonical: HashSet<String>,
+    pub rules: RuleSet,
     pub accept_description: String,
 }
 
 impl Default for EquationConstraint {
     fn default() -> Self {
-        ACCEPT_ANY_EQUATION_RC.with(|accept_anything| Self {
-            accept: accept_anything.clone(),
-            a_constraint: ExpressionNumberConstraint::default(),
-            b_constraint: ExpressionNumberConstraint::default(),
-            b2_constraint: ExpressionNumberConstraint::default(),
-            c_constraint: ExpressionNumberConstraint::default(),
-            operator: HashMap::new(),
-            num_ops: 1..=NERDLE_MAX_OPS,
-            accept_description: "No further contraints".to_string(),
-        })
+        EquationConstraint::for_length(NERDLE_CHARACTERS as usize)
     }
 }
 
 impl EquationConstraint {
+    // Anything goes for equations of this many characters, like 10 for Maxi Nerdle
+    pub fn for_length(chars: usize) -> Self {
+        let largest = largest_number(chars);
+        Self {
+            operands: Vec::new(),
+            // Whether the result can be 0 or negative is up to the rules
+            c_constraint: ExpressionNumberConstraint {
+                range: -largest..=largest,
+                description: format!("Default range: -{}..{}", largest, largest),
+                pattern: None,
+            },
+            operator: HashMap::new(),
+            num_ops: 1..=max_ops_for_length(chars),
+            positions: vec![NERDLE_VALID_CHAR_BYTES.iter().cloned().collect(); chars],
+            counts: HashMap::new(),
+            excluded_canonical: HashSet::new(),
+            rules: RuleSet::default(),
+            accept_description: "No further contraints".to_string(),
+        }
+    }
+
+    // How many characters equations have
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
     pub fn accept(&self, eq: &Equation) -> Result<(),NoMatchFound> {
-        let accept_a_op1_b = |a: &ExpressionNumber, op1: &Box<dyn ExpressionOperator>, b: &ExpressionNumber, num_operators: &u32| {
-            self.a_constraint.accept(&a)?;
-            self.b_constraint.accept(&b)?;
-            if !self.num_ops.contains(&num_operators) { // 1 operator
-                return Err(NoMatchFound { message: format!("Equation had 1 operator: {}", self)});
-            }
-            if !self.can_have_op(&op1) {
-                return Err(NoMatchFound { message: format!("Equation had disallowed operator {}: {}", &op1, self)});
+        self.c_constraint.accept(&eq.res)?;
+        self.accept_chars(eq)?;
+        // Numbers and operators take turns, starting and ending with a number
+        let parts = &eq.expr.parts;
+        if !matches!(parts.last(), Some(ExpressionPart::Number(_))) {
+            return Err(NoMatchFound { message: format!("Unrecognized pattern for equation: {}", &eq)});
+        }
+        let mut num_operators = 0;
+        for (i, part) in parts.iter().enumerate() {
+            match (i % 2, part) {
+                (0, ExpressionPart::Number(num)) => {
+                    if let Some(operand) = self.operands.get(i / 2) {
+                        operand.accept(num)?;
+                    }
+                },
+                (1, ExpressionPart::Operator(op)) => {
+                    num_operators += 1;
+                    if !self.can_have_op(op) {
+                        return Err(NoMatchFound { message: format!("Equation had disallowed operator {}: {}", op, self)});
+                    }
+                },
+                _ => return Err(NoMatchFound { message: format!("Unrecognized pattern for equation: {}", &eq)}),
             }
-            Ok(())
-        };
+        }
+        if !self.num_ops.contains(&num_operators) {
+            return Err(NoMatchFound { message: format!("Equation had {} operator(s): {}", num_operators, self)});
+        }
+        if let Err(err) = self.rules.check(eq) {
+            return Err(NoMatchFound { message: format!("Rules {} failed: {}", self.rules, err)});
+        }
+        if !self.excluded_canonical.is_empty() && self.excluded_canonical.contains(&eq.canonical()) {
+            return Err(NoMatchFound { message: format!("Equation {} is a commuted form of an excluded equation", eq)})
+        }
+        Ok(())
+    }
 
-        self.c_constraint.accept(&eq.res)?;
-        match eq.expr.parts.as_slice() {
-            [ExpressionPart::Number(a), ExpressionPart::Operator(op1), ExpressionPart::Number(b)] => {
-                accept_a_op1_b(&a, &op1, &b, &1)?;
-            },
-            [ExpressionPart::Number(a), ExpressionPart::Operator(op1), ExpressionPart::Number(b), ExpressionPart::Operator(op2), ExpressionPart::Number(b2)] => {
-                accept_a_op1_b(&a, &op1, &b, &2)?;
-                self.b2_constraint.accept(&b2)?;
-                if !self.can_have_op(&op2) {
-                    return Err(NoMatchFound { message: format!("Equation had disallowed operator: {}", &self)});
-                }
-            },
-            _ => return Err(NoMatchFound { message: format!("Unrecognized pattern for equation: {}", &eq)})
+    fn accept_chars(&self, eq: &Equation) -> Result<(), NoMatchFound> {
+        let eq_str = eq.to_string();
+        if eq_str.len() != self.len() {
+            return Err(NoMatchFound { message: format!("Equation {} is not {} characters long", eq, self.len())});
+        }
+        for (pos, ch) in eq_str.bytes().enumerate() {
+            if !self.positions.get(pos).map(|allowed| allowed.contains(&ch)).unwrap_or(false) {
+                return Err(NoMatchFound { message: format!("Equation {} can't have {} at position {}", eq, ch as char, pos)});
+            }
         }
-        if !(self.accept)(&eq) {
-            return Err(NoMatchFound { message: format!("Accept function failed for constraint: {}", self)})
+        for (ch, range) in self.counts.iter() {
+            let count = eq_str.bytes().filter(|eq_ch| eq_ch == ch).count() as u32;
+            if !range.contains(&count) {
+                return Err(NoMatchFound { message: format!("Equation {} has {} of {}, not {}..={}", eq, count, *ch as char, range.start(), range.end())});
+            }
         }
         Ok(())
     }
 
+    // Whether any equation fits.  The search is exhaustive, so false means there really are none, and it stops at
+    // the first match.  Nothing in the binary needs just a yes or no yet, but it's part of what callers can ask.
+    #[allow(dead_code)]
+    pub fn is_satisfiable(&self) -> bool {
+        let mut found = false;
+        eqgen_search(self, &mut |_| {
+            found = true;
+            false
+        });
+        found
+    }
+
+    pub fn count(&self) -> usize {
+        let mut count = 0;
+        eqgen_search(self, &mut |_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    // Every equation which fits, in a fixed order.  The whole search runs and collects its results before the first
+    // one comes back, so taking just a few still costs as much as count(); use eqgen_search to stop early.
+    pub fn iter(&self) -> impl Iterator<Item = Equation> {
+        let mut all = Vec::new();
+        eqgen_search(self, &mut |eq| {
+            all.push(eq);
+            true
+        });
+        all.into_iter()
+    }
+
     pub fn can_have_op_byte(&self, byte: u8) -> bool {
         self.operator.get(&byte).map(|range| range.end() >= &1).unwrap_or(true)
     }
@@ -182,12 +251,169 @@ impl fmt::Display for EquationConstraint {
         }
         write!(f, ")")?;
 
-        write!(f, ", a: {}, b: {}, b2: {}, c: {}", &self.a_constraint, &self.b_constraint, &self.b2_constraint, &self.c_constraint)?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            write!(f, ", operand {}: {}", i + 1, operand)?;
+        }
+        write!(f, ", c: {}", &self.c_constraint)?;
 
         write!(f, ", {}", &self.accept_description)
     }
 }
 
+// A constraint written as text, like "??+??=?? ; 7 notin ; 3 count 1..2 ; pos5 in {1,4}".  Clauses are separated
+// by semicolons and can be:
+//   a pattern with ? for any character, which also sets how long equations are (NERDLE_CHARACTERS if there's none)
+//   "C in" or "C notin", for whether character C appears at all
+//   "C count N" or "C count N..M", for how many times it appears
+//   "posP in {A,B}" or "posP notin {A,B}", for which characters can be at position P, numbered from 1
+//   "ops N" or "ops N..M", for how many operators there are
+//   "rules R", for a RuleSet other than the classic one, like "rules zero_operands,negative_results"
+// Only these parts of a constraint can be written as text, so pattern() leaves out digit patterns, excluded
+// equations and the ranges for each number.
+impl FromStr for EquationConstraint {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let clauses: Vec<&str> = input.split(';').map(|clause| clause.trim()).filter(|clause| !clause.is_empty()).collect();
+        let chars = clauses.iter()
+            .find(|clause| clause.split_whitespace().count() == 1)
+            .map(|pattern| pattern.len())
+            .unwrap_or(NERDLE_CHARACTERS as usize);
+        if chars < MIN_EQUATION_LENGTH {
+            return Err(NerdleError { message: format!("Equations need at least {} characters, not {}", MIN_EQUATION_LENGTH, chars)});
+        }
+        let mut constraint = EquationConstraint {
+            accept_description: format!("Pattern \"{}\"", input.trim()),
+            ..EquationConstraint::for_length(chars)
+        };
+        for clause in clauses {
+            constraint.add_clause(clause)
+                .map_err(|err| NerdleError { message: format!("Invalid clause '{}': {}", clause, err.message)})?;
+        }
+        Ok(constraint)
+    }
+}
+
+impl EquationConstraint {
+    fn add_clause(&mut self, clause: &str) -> Result<(), NerdleError> {
+        let words: Vec<&str> = clause.split_whitespace().collect();
+        match words.as_slice() {
+            [pattern] => {
+                if pattern.len() != self.len() {
+                    return Err(NerdleError { message: format!("pattern is {} characters, not {}", pattern.len(), self.len())});
+                }
+                for (pos, ch) in pattern.bytes().enumerate() {
+                    if ch != b'?' {
+                        let ch = pattern_char(&(ch as char).to_string())?;
+                        self.positions[pos].retain(|allowed| *allowed == ch);
+                    }
+                }
+            },
+            [pos, in_or_notin, set @ ..] if pos.starts_with("pos") => {
+                let pos = usize::from_str(&pos[3..])
+                    .map_err(|err| NerdleError { message: format!("invalid position '{}': {}", &pos[3..], err)})?;
+                if pos < 1 || pos > self.len() {
+                    return Err(NerdleError { message: format!("position {} is not 1 to {}", pos, self.len())});
+                }
+                let set = set.join("");
+                let set = set.trim_start_matches('{').trim_end_matches('}');
+                let chars = set.split(',').filter(|ch| !ch.is_empty()).map(pattern_char).collect::<Result<HashSet<u8>, NerdleError>>()?;
+                match *in_or_notin {
+                    "in" => self.positions[pos - 1].retain(|ch| chars.contains(ch)),
+                    "notin" => self.positions[pos - 1].retain(|ch| !chars.contains(ch)),
+                    _ => return Err(NerdleError { message: format!("expected in or notin, not '{}'", in_or_notin)}),
+                }
+            },
+            ["ops", range] => self.num_ops = range_intersect(&self.num_ops, &pattern_range(range)?),
+            ["rules", rules] => self.rules = RuleSet::from_str(rules)?,
+            [ch, "in"] => self.add_count(pattern_char(ch)?, 1..=self.len() as u32),
+            [ch, "notin"] => self.add_count(pattern_char(ch)?, 0..=0),
+            [ch, "count", range] => self.add_count(pattern_char(ch)?, pattern_range(range)?),
+            _ => return Err(NerdleError { message: "unrecognized clause".to_string()}),
+        }
+        Ok(())
+    }
+
+    fn add_count(&mut self, ch: u8, range: RangeInclusive<u32>) {
+        let range = match self.counts.get(&ch) {
+            Some(existing) => range_intersect(existing, &range),
+            None => range,
+        };
+        self.counts.insert(ch, range);
+    }
+
+    // This constraint written in the form FromStr reads
+    pub fn pattern(&self) -> String {
+        let all: HashSet<u8> = NERDLE_VALID_CHAR_BYTES.iter().cloned().collect();
+        let mut pattern = String::new();
+        let mut clauses = Vec::new();
+        for (pos, allowed) in self.positions.iter().enumerate() {
+            if allowed.len() == 1 {
+                pattern.push(*allowed.iter().next().unwrap() as char);
+                continue;
+            }
+            pattern.push('?');
+            if *allowed != all {
+                let excluded: HashSet<u8> = all.difference(allowed).cloned().collect();
+                if allowed.len() <= excluded.len() {
+                    clauses.push(format!("pos{} in {{{}}}", pos + 1, pattern_set(allowed)));
+                } else {
+                    clauses.push(format!("pos{} notin {{{}}}", pos + 1, pattern_set(&excluded)));
+                }
+            }
+        }
+        clauses.insert(0, pattern);
+
+        let mut counts: Vec<(&u8, &RangeInclusive<u32>)> = self.counts.iter().collect();
+        counts.sort_by_key(|(ch, _)| **ch);
+        for (ch, range) in counts {
+            clauses.push(match (*range.start(), *range.end()) {
+                (0, 0) => format!("{} notin", *ch as char),
+                (1, max) if max as usize == self.len() => format!("{} in", *ch as char),
+                _ => format!("{} count {}", *ch as char, pattern_range_str(range)),
+            });
+        }
+        if self.num_ops != (1..=max_ops_for_length(self.len())) {
+            clauses.push(format!("ops {}", pattern_range_str(&self.num_ops)));
+        }
+        if self.rules != RuleSet::default() {
+            clauses.push(format!("rules {}", self.rules));
+        }
+        clauses.join(" ; ")
+    }
+}
+
+fn pattern_char(input: &str) -> Result<u8, NerdleError> {
+    let input = input.trim();
+    match input.as_bytes() {
+        [ch] if NERDLE_VALID_CHAR_BYTES.contains(ch) => Ok(*ch),
+        _ => Err(NerdleError { message: format!("'{}' is not a Nerdle character", input)}),
+    }
+}
+
+fn pattern_range(input: &str) -> Result<RangeInclusive<u32>, NerdleError> {
+    let parse = |num: &str| u32::from_str(num)
+        .map_err(|err| NerdleError { message: format!("invalid number '{}': {}", num, err)});
+    match input.split_once("..") {
+        Some((start, end)) => Ok(parse(start)?..=parse(end.trim_start_matches('='))?),
+        None => parse(input).map(|num| num..=num),
+    }
+}
+
+fn pattern_range_str(range: &RangeInclusive<u32>) -> String {
+    if range.start() == range.end() {
+        range.start().to_string()
+    } else {
+        format!("{}..{}", range.start(), range.end())
+    }
+}
+
+fn pattern_set(chars: &HashSet<u8>) -> String {
+    let mut chars: Vec<&u8> = chars.iter().collect();
+    chars.sort();
+    chars.iter().map(|ch| (**ch as char).to_string()).collect::<Vec<String>>().join(",")
+}
+
 #[derive(Clone)]
 pub struct NoMatchFound {
     pub message: String,
@@ -222,7 +448,7 @@ fn range_intersect_test() {
     assert!(range_intersect(&(0..=10), &(15..=20)).is_empty());
 }
 
-pub fn range_for_digits(digits: usize, allow_zero: bool) -> RangeInclusive<i32> {
+pub fn range_for_digits(digits: usize, allow_zero: bool) -> RangeInclusive<i64> {
     let single_digit_range_start = if allow_zero {
         0
     } else {
@@ -230,24 +456,74 @@ pub fn range_for_digits(digits: usize, allow_zero: bool) -> RangeInclusive<i32>
     };
     match digits {
         1 => single_digit_range_start..=9,
-        2 => 10..=99,
-        3 => 100..=999,
-        4 => 1000..=9999,
-        _ => 1..=NERDLE_NUM_MAX,
+        _ => 10i64.saturating_pow(digits as u32 - 1)..=(10i64.saturating_pow(digits as u32) - 1),
     }
 }
 
-pub fn range_for_digits_or_less(digits: usize, allow_zero: bool) -> RangeInclusive<i32> {
-    let single_digit_range_start = if allow_zero {
-        0
-    } else {
-        1
-    };
-    match digits {
-        1 => single_digit_range_start..=9,
-        2 => single_digit_range_start..=99,
-        3 => single_digit_range_start..=999,
-        4 => single_digit_range_start..=9999,
-        _ => single_digit_range_start..=NERDLE_NUM_MAX,
+// The shortest equations are like "1+2=3"
+pub const MIN_EQUATION_LENGTH: usize = 5;
+
+// Every operator needs a number after it, and there has to be room for the equal sign and the result
+pub fn max_ops_for_length(chars: usize) -> u32 {
+    (chars.saturating_sub(3) / 2) as u32
+}
+
+// The largest number that fits in an equation of this many characters, which also needs an operator, another
+// operand, the equal sign and a result of at least a digit each
+pub fn largest_number(chars: usize) -> i64 {
+    *range_for_digits(chars.saturating_sub(4).max(1), true).end()
+}
+
+#[test]
+fn equation_constraint_count_test() {
+    let mut constraint = EquationConstraint::default();
+    for (pos, ch) in "????=105".bytes().enumerate() {
+        if ch != b'?' {
+            constraint.positions[pos] = HashSet::from([ch]);
+        }
     }
+    assert!(constraint.is_satisfiable());
+    assert_eq!(constraint.count(), 14);
+    let all: Vec<String> = constraint.iter().map(|eq| eq.to_string()).collect();
+    assert_eq!(all.len(), 14);
+    assert!(all.contains(&"3*35=105".to_string()));
+    assert!(all.contains(&"99+6=105".to_string()));
+
+    constraint.counts.insert(b'+', 0..=0);
+    constraint.counts.insert(b'*', 0..=0);
+    assert!(!constraint.is_satisfiable());
+    assert_eq!(constraint.count(), 0);
+    assert_eq!(constraint.iter().count(), 0);
+}
+
+#[test]
+fn equation_constraint_pattern_test() {
+    let constraint = EquationConstraint::from_str("12?34=?6 ; 7 notin ; 3 count 1..2 ; pos7 in {1,4}").unwrap();
+    assert_eq!(constraint.positions[0], HashSet::from([b'1']));
+    assert_eq!(constraint.positions[6], HashSet::from([b'1', b'4']));
+    assert_eq!(constraint.counts.get(&b'7'), Some(&(0..=0)));
+    assert_eq!(constraint.counts.get(&b'3'), Some(&(1..=2)));
+    assert_eq!(constraint.iter().map(|eq| eq.to_string()).collect::<Vec<String>>(), vec!["12+34=46"]);
+
+    // Printed back in a fixed order, which reads back the same
+    let pattern = constraint.pattern();
+    assert_eq!(pattern, "12?34=?6 ; pos7 in {1,4} ; 3 count 1..2 ; 7 notin");
+    assert_eq!(EquationConstraint::from_str(&pattern).unwrap().pattern(), pattern);
+
+    let pattern = "??????=? ; pos1 notin {9} ; + in ; / notin ; ops 2";
+    assert_eq!(EquationConstraint::from_str(pattern).unwrap().pattern(), pattern);
+    assert_eq!(EquationConstraint::default().pattern(), "????????");
+
+    // The pattern sets the length, and with it how many operators there can be
+    let maxi = EquationConstraint::from_str("??????????").unwrap();
+    assert_eq!(maxi.len(), 10);
+    assert_eq!(maxi.num_ops, 1..=3);
+    assert_eq!(maxi.pattern(), "??????????");
+    assert_eq!(EquationConstraint::from_str("?????????? ; ops 3").unwrap().pattern(), "?????????? ; ops 3");
+
+    assert!(EquationConstraint::from_str("????????? ; ????????").is_err());
+    assert!(EquationConstraint::from_str("?+?=").is_err());
+    assert!(EquationConstraint::from_str("x notin").is_err());
+    assert!(EquationConstraint::from_str("pos9 in {1}").is_err());
+    assert!(EquationConstraint::from_str("3 count lots").is_err());
 }
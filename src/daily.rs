@@ -0,0 +1,279 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+
+use crate::answers::AnswerList;
+use crate::eq::Equation;
+use crate::host::GameMode;
+use crate::nerdle::{NerdleResult, NerdleError};
+use crate::share::NerdleShare;
+
+// Daily puzzles: everyone playing the same date and mode gets the same answer.  Each mode has its own fixed
+// shuffle of the answer list, and each day takes the next answer from it, so answers don't repeat until the
+// whole list has been used.
+
+// Puzzle 1 was on launch day
+const DAILY_LAUNCH: NerdleDate = NerdleDate { year: 2022, month: 1, day: 20 };
+const DAILY_SEED: u64 = 0x6e65_7264_6c65;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NerdleDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl NerdleDate {
+    // Today in UTC, so everyone's day changes at the same moment
+    pub fn today() -> NerdleDate {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        NerdleDate::from_days((secs / 86400) as i64)
+    }
+
+    // Days since 1970-01-01, using the proleptic Gregorian calendar
+    pub fn days(&self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    pub fn from_days(days: i64) -> NerdleDate {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        NerdleDate { year, month, day }
+    }
+
+    // Puzzle number, or None before launch
+    pub fn puzzle(&self) -> Option<u32> {
+        let days = self.days() - DAILY_LAUNCH.days();
+        if days < 0 {
+            None
+        } else {
+            Some(days as u32 + 1)
+        }
+    }
+}
+
+impl FromStr for NerdleDate {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || NerdleError { message: format!("Invalid date '{}', expected YYYY-MM-DD", input)};
+        let parts: Vec<&str> = input.trim().split('-').collect();
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        let date = NerdleDate {
+            year: i32::from_str(parts[0]).map_err(|_| invalid())?,
+            month: u32::from_str(parts[1]).map_err(|_| invalid())?,
+            day: u32::from_str(parts[2]).map_err(|_| invalid())?,
+        };
+        // Round-tripping through days catches Feb 30 and friends
+        if date.month < 1 || date.month > 12 || date.day < 1 || NerdleDate::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl fmt::Display for NerdleDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// The curated answers daily puzzles are drawn from when no list is given.  They are built into the binary, so
+// everyone gets the same puzzle no matter where it is run from.
+const DAILY_ANSWERS: &str = include_str!("../daily_answers.txt");
+
+pub fn daily_answers() -> Result<AnswerList, NerdleError> {
+    AnswerList::parse(DAILY_ANSWERS, "daily_answers.txt")
+}
+
+// Puzzles which would be dull, like multiplying or dividing by 1, which the curated list leaves out
+#[cfg(test)]
+fn is_trivial(eq: &str) -> bool {
+    let lhs = eq.split('=').next().unwrap_or("");
+    let mut tokens: Vec<String> = Vec::new();
+    for ch in lhs.chars() {
+        match tokens.last_mut() {
+            Some(last) if ch.is_ascii_digit() && last.chars().all(|c| c.is_ascii_digit()) => last.push(ch),
+            _ => tokens.push(ch.to_string()),
+        }
+    }
+    let is_one = |pos: Option<usize>| pos.and_then(|pos| tokens.get(pos)).map(|token| token == "1").unwrap_or(false);
+    tokens.iter().enumerate().any(|(i, token)| match token.as_str() {
+        "*" => is_one(i.checked_sub(1)) || is_one(Some(i + 1)),
+        "/" => is_one(Some(i + 1)),
+        _ => false,
+    })
+}
+
+pub fn daily_answer(date: &NerdleDate, mode: GameMode, answers: &[Equation]) -> Result<Equation, NerdleError> {
+    let puzzle = date.puzzle()
+        .ok_or_else(|| NerdleError { message: format!("No daily puzzle before {}", DAILY_LAUNCH)})?;
+    if answers.is_empty() {
+        return Err(NerdleError { message: "No answers to choose from".to_string()});
+    }
+    // Sort first so the shuffle only depends on which answers there are, not the order they were loaded in
+    let mut answers: Vec<&Equation> = answers.iter().collect();
+    answers.sort_by_cached_key(|eq| eq.to_string());
+    // A named generator rather than StdRng, whose algorithm may change between rand versions and with it every
+    // daily puzzle
+    let mut rng = ChaCha8Rng::seed_from_u64(DAILY_SEED ^ mode as u64);
+    answers.shuffle(&mut rng);
+    Ok(answers[(puzzle as usize - 1) % answers.len()].clone())
+}
+
+// One finished daily game, as kept in the archive
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyRecord {
+    pub date: NerdleDate,
+    pub mode: GameMode,
+    pub share: NerdleShare,
+}
+
+impl FromStr for DailyRecord {
+    type Err = NerdleError;
+
+    // e.g. "2022-01-20 classic G-GY-GY- GGGGGGGG"
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut words = input.split_whitespace();
+        let date = NerdleDate::from_str(words.next().unwrap_or(""))?;
+        let mode = GameMode::from_str(words.next().unwrap_or(""))?;
+        let rows = words.map(NerdleResult::from_str).collect::<Result<Vec<NerdleResult>, NerdleError>>()?;
+        Ok(DailyRecord { date, mode, share: NerdleShare::new(date.puzzle(), rows) })
+    }
+}
+
+impl fmt::Display for DailyRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.date, self.mode)?;
+        for row in self.share.rows.iter() {
+            write!(f, " {}", row)?;
+        }
+        Ok(())
+    }
+}
+
+// Results of past daily games, one per line in a text file
+pub struct DailyArchive {
+    file_name: String,
+}
+
+impl DailyArchive {
+    pub fn new(file_name: &str) -> DailyArchive {
+        DailyArchive {
+            file_name: file_name.to_string(),
+        }
+    }
+
+    // A missing archive just means no games have been played yet
+    pub fn records(&self) -> Result<Vec<DailyRecord>, NerdleError> {
+        let file = match File::open(&self.file_name) {
+            Ok(file) => file,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|err| NerdleError { message: format!("Error reading '{}': {}", self.file_name, err)})?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            records.push(DailyRecord::from_str(&line)?);
+        }
+        Ok(records)
+    }
+
+    pub fn find(&self, date: &NerdleDate, mode: GameMode) -> Result<Option<DailyRecord>, NerdleError> {
+        Ok(self.records()?.into_iter().find(|record| &record.date == date && record.mode == mode))
+    }
+
+    pub fn add(&self, record: &DailyRecord) -> Result<(), NerdleError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.file_name)
+            .map_err(|err| NerdleError { message: format!("Error opening '{}': {}", self.file_name, err)})?;
+        writeln!(file, "{}", record)
+            .map_err(|err| NerdleError { message: format!("Error writing '{}': {}", self.file_name, err)})
+    }
+}
+
+#[test]
+fn nerdle_date_test() {
+    assert_eq!(NerdleDate::from_str("1970-01-01").unwrap().days(), 0);
+    assert_eq!(NerdleDate::from_str("2022-01-20").unwrap().puzzle(), Some(1));
+    assert_eq!(NerdleDate::from_str("2022-02-01").unwrap().puzzle(), Some(13));
+    assert_eq!(NerdleDate::from_str("2021-12-31").unwrap().puzzle(), None);
+    assert_eq!(NerdleDate::from_days(NerdleDate::from_str("2024-02-29").unwrap().days() + 1).to_string(), "2024-03-01");
+    assert!(NerdleDate::from_str("2023-02-29").is_err());
+    assert!(NerdleDate::from_str("2023-13-01").is_err());
+    assert!(NerdleDate::from_str("yesterday").is_err());
+}
+
+#[test]
+fn daily_answer_test() {
+    let answers: Vec<Equation> = ["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108"]
+        .iter().map(|eq| Equation::from_str(eq).unwrap()).collect();
+    let date = NerdleDate::from_str("2022-03-01").unwrap();
+    let answer = daily_answer(&date, GameMode::Classic, &answers).unwrap();
+    // Pinned, so a change to the shuffle which would change everyone's puzzles gets noticed
+    assert_eq!(answer.to_string(), "9*12=108");
+
+    // Same answer no matter what order the list is in
+    let reversed: Vec<Equation> = answers.iter().rev().cloned().collect();
+    assert_eq!(daily_answer(&date, GameMode::Classic, &reversed).unwrap().to_string(), answer.to_string());
+
+    // Consecutive days don't repeat until the list runs out
+    let week: Vec<String> = (0..5)
+        .map(|day| daily_answer(&NerdleDate::from_days(date.days() + day), GameMode::Classic, &answers).unwrap().to_string())
+        .collect();
+    let mut unique = week.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 5);
+
+    assert!(daily_answer(&NerdleDate::from_str("2000-01-01").unwrap(), GameMode::Classic, &answers).is_err());
+}
+
+#[test]
+fn daily_answers_test() {
+    let list = daily_answers().unwrap();
+    assert!(list.answers.len() >= 365);
+    assert!(list.validate().is_empty());
+    assert!(list.answers.iter().all(|eq| !is_trivial(&eq.to_string())));
+}
+
+#[test]
+fn is_trivial_test() {
+    assert!(is_trivial("1*23=23"));
+    assert!(is_trivial("46/1-2=44"));
+    assert!(is_trivial("5+1*8=13"));
+    assert!(!is_trivial("1+23=24"));
+    assert!(!is_trivial("11*2=22"));
+    assert!(!is_trivial("8/2+1=5"));
+}
+
+#[test]
+fn daily_record_test() {
+    let record = DailyRecord::from_str("2022-01-21 commutative G-GY-GY- GGGGGGGG").unwrap();
+    assert_eq!(record.mode, GameMode::Commutative);
+    assert_eq!(record.share.puzzle, Some(2));
+    assert_eq!(record.share.turns(), Some(2));
+    assert_eq!(record.to_string(), "2022-01-21 commutative G-GY-GY- GGGGGGGG");
+    assert!(DailyRecord::from_str("2022-01-21 sideways").is_err());
+}
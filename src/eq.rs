@@ -1,11 +1,15 @@
 use std::fmt;
 use std::str::FromStr;
 
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error;
+
 use crate::expr::Expression;
 use crate::expr::ExpressionPart;
 use crate::expr::ExpressionNumber;
 use crate::expr::InvalidExpressionError;
 
+#[derive(Clone)]
 pub struct Equation {
     pub expr: Expression,
     pub res: ExpressionNumber,
@@ -20,6 +24,15 @@ impl Equation {
     pub fn len(&self) -> Result<usize, InvalidEquationError> {
         Ok(self.expr.len()? + self.res.len()? + 1)
     }
+
+    pub fn canonical(&self) -> String {
+        format!("{}={}", self.expr.canonical(), self.res)
+    }
+
+    // The real game accepts a guess which only reorders the terms of the answer, like 20+30=50 for 30+20=50
+    pub fn is_commutation_of(&self, other: &Equation) -> bool {
+        self.canonical() == other.canonical()
+    }
 }
 
 impl FromStr for Equation {
@@ -30,11 +43,17 @@ impl FromStr for Equation {
         let expr = split.next().ok_or_else(|| InvalidEquationError { message: format!("Could not find equal sign in '{}'", input) } )?;
         let expr = Expression::from_str(&expr)?;
         let res = split.next().ok_or_else(|| InvalidEquationError { message: format!("Could not find value after equal sign '{}'", input) } )?;
+        // The result may be negative, though whether that's allowed is up to the RuleSet
+        let (negative, res) = match res.strip_prefix('-') {
+            Some(res) => (true, res),
+            None => (false, res),
+        };
         let res = Expression::from_str(&res)?;
         if res.parts.len() != 1 {
             return Err(InvalidEquationError { message: format!("RHS must be a simple number in '{}'", input) } );
         }
         let res = match &res.parts[0] {
+            ExpressionPart::Number(n) if negative => ExpressionNumber::new(-n.int_value()?),
             ExpressionPart::Number(n) => n.clone(),
             _ => return Err(InvalidEquationError { message: format!("RHS must be a simple number in '{}'", input) } )
         };
@@ -54,6 +73,20 @@ impl fmt::Display for Equation {
     }
 }
 
+// Serialized as its text, like "12+34=46"
+impl Serialize for Equation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Equation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Equation::from_str(&text).map_err(D::Error::custom)
+    }
+}
+
 #[derive(Clone)]
 pub struct InvalidEquationError {
     message: String,
@@ -1,496 +1,493 @@
-use std::rc::Rc;
-use std::collections::HashMap;
+use std::str::FromStr;
+use std::cmp::{min, max};
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 
 use crate::eq::Equation;
-use crate::nerdle::{NERDLE_CHARACTERS, NERDLE_A_MAX};
-use crate::expr::{Expression, ExpressionPart, ExpressionOperator, ExpressionOperatorPlus, ExpressionOperatorMinus, ExpressionOperatorTimes, ExpressionOperatorDivide, ExpressionOperatorEnum};
-use crate::constraint::{find_num_with_constraint, EquationConstraint, ExpressionNumberConstraint, NoMatchFound, range_for_digits_or_less};
-use crate::util::{range_rand_or_only};
-
-const ATTEMPTS: u32 = 10000;
-
-// TODO: CopyPasta from main.rs
-macro_rules! skip_fail {
-    ($res:expr, $message:expr) => {
-        match $res {
-            Ok(val) => val,
-            Err(_e) => {
-                // println!("{} (Error {})", $message, e);
-                continue;
+use crate::expr::{Expression, ExpressionPart};
+use crate::constraint::{EquationConstraint, NoMatchFound, range_for_digits, range_intersect, max_ops_for_length};
+
+const OPERATOR_BYTES: &[u8] = b"+-*/";
+
+// Pick one of the equations matching the constraint, each equally likely.  Taking the first match from a search
+// in random order isn't uniform: an operand length is as likely as any other, so equations with short operands
+// would come up far more often than their share.
+pub fn eqgen_constrained(constraint: &EquationConstraint) -> Result<Equation, NoMatchFound> {
+    eqgen_weighted(constraint, &|_| 1.0)
+}
+
+// Pick one of the equations matching the constraint, with chances in proportion to weight.  Equations with no
+// weight are never picked.
+pub fn eqgen_weighted(constraint: &EquationConstraint, weight: &dyn Fn(&Equation) -> f64) -> Result<Equation, NoMatchFound> {
+    // Weighted reservoir sampling: each match replaces the one kept so far with its share of the weight so far
+    let mut total = 0.0;
+    let mut chosen = None;
+    eqgen_search(constraint, &mut |eq| {
+        let eq_weight = weight(&eq);
+        if eq_weight > 0.0 {
+            total += eq_weight;
+            if rand::random::<f64>() * total < eq_weight {
+                chosen = Some(eq);
             }
         }
-    };
+        true
+    });
+    chosen.ok_or_else(|| NoMatchFound { message: format!("No equation matches constraint {}", constraint) })
 }
 
-pub fn eqgen_constrained(constraint: &EquationConstraint) -> Result<Equation, NoMatchFound> {
-    // println!("Incoming constraint: {}", constraint);
+pub fn eqgen() -> Result<Equation, NoMatchFound> {
+    eqgen_constrained(&EquationConstraint::default())
+}
 
-    for _try in 1..ATTEMPTS {
-        let mut parts: Vec<ExpressionPart> = Vec::new();
-        let mut remaining_chars = NERDLE_CHARACTERS as usize;
+// Every equation of NERDLE_CHARACTERS characters allowed by the default constraint, in a fixed order.
+// There are only tens of thousands of these, so it is practical to just check them all.
+pub fn eqgen_all() -> Vec<Equation> {
+    eqgen_all_constrained(&EquationConstraint::default())
+}
 
-        let(op1, op2_opt) = gen_ops(constraint)?;
-        let op2_str = match &op2_opt {
-            Some(op) => op.to_string(),
-            None => "None".to_string()
-        };
+// Every equation the constraint allows, in the same fixed order
+pub fn eqgen_all_constrained(constraint: &EquationConstraint) -> Vec<Equation> {
+    let mut all = Vec::new();
+    eqgen_search(constraint, &mut |eq| {
+        all.push(eq);
+        true
+    });
+    all
+}
 
-        // println!("Trying with op1 {}, op2 {}", &op1, &op2_opt.map(|op| op.to_string()).unwrap_or("None"));
-        let operand_range = if op2_opt.is_none() {
-            1..=NERDLE_A_MAX
-        } else {
-            1..=99
-        };
+// Search for equations an operand at a time, left to right, calling visit with each match until it returns false.
+// Each operand only tries values whose digits the constraint allows in those positions, character counts are
+// checked as we go, the last operand only tries values which could give a result of the right length, and the
+// result is worked out rather than searched for.  The search is exhaustive, so if nothing is visited nothing
+// matches the constraint.
+pub fn eqgen_search(constraint: &EquationConstraint, visit: &mut dyn FnMut(Equation) -> bool) {
+    let mut search = EquationSearch {
+        constraint,
+        limited: constraint.counts.keys().chain(constraint.operator.keys()).cloned().collect::<HashSet<u8>>().into_iter().collect(),
+        prefix: String::new(),
+        counts: HashMap::new(),
+    };
+    let min_ops: u32 = OPERATOR_BYTES.iter().map(|op| search.min_count(*op)).sum();
+    for num_ops in constraint.num_ops.clone() {
+        if num_ops < min_ops || num_ops > max_ops_for_length(constraint.len()) {
+            continue;
+        }
+        // Leave room for the operators and the equal sign
+        let digits = constraint.len() - num_ops as usize - 1;
+        if !search.expr(None, num_ops as usize + 1, digits, visit) {
+            return;
+        }
+    }
+}
 
-        let a_base_range = match op1 {
-            ExpressionOperatorEnum::Divide if op2_opt.is_none() => 100..=999,
-            _ => operand_range.clone()
-        };
-        let a_base_constraint = ExpressionNumberConstraint {
-            description: format!("{}..={} (from op1 {}, op2 {})", a_base_range.start(), a_base_range.end(), &op1, &op2_str),
-            range: a_base_range,
-            ..Default::default()
-        };
-        let a_constraint = &ExpressionNumberConstraint::intersect(&a_base_constraint, &constraint.a_constraint);
-        // println!("Finding value for a");
-        let a = skip_fail!(find_num_with_constraint(a_constraint), "Failed to generate a");
-        let a_len = skip_fail!(a.len(), "a does not have valid length");
-        remaining_chars -= a_len;
-        remaining_chars -= 1; // op1
-        // println!("Generated a {} from constraint: {}", a, &a_constraint);
-
-        let b_remaining_chars = remaining_chars
-            - if op2_opt.is_some() { 2 } else { 0 } // second op and b2 (if present)
-            - 2 // =c
-        ;
-        let b_range = range_for_digits_or_less(b_remaining_chars, false);
-        let mut b_base_constraint = ExpressionNumberConstraint {
-            description: format!("{}..={} for {}-digit number", b_range.start(), b_range.end(), b_remaining_chars),
-            range: b_range,
-            ..Default::default()
-        };
+struct EquationSearch<'a> {
+    constraint: &'a EquationConstraint,
+    // Characters with a minimum or maximum count
+    limited: Vec<u8>,
+    prefix: String,
+    counts: HashMap<u8, u32>,
+}
 
-        // Optimize b selection for one-operator case
-        if op2_str.is_empty() {
-            if let ExpressionPart::Operator(op1_obj) = op2op(&op1) {
-                let a_clone = a.clone();
-                let c_constraint_accept = constraint.c_constraint.accept.clone();
-                b_base_constraint.accept = Rc::new(move |b| {
-                    let c = op1_obj.operate(&a_clone, &b);
-                    match c {
-                        Err(_) => false,
-                        Ok(c) => c_constraint_accept(&c)
+impl EquationSearch<'_> {
+    // Recursively add operands to prefix.  digits is how many digits are left for the remaining operands and the
+    // result.  last_op is the operator just added to prefix, if any.  Returns false once visit has asked to stop.
+    fn expr(&mut self, last_op: Option<u8>, operands: usize, digits: usize, visit: &mut dyn FnMut(Equation) -> bool) -> bool {
+        let prefix_len = self.prefix.len();
+        // Every later operand and the result need at least one digit
+        for len in 1..=(digits - operands) {
+            // Whatever follows this operand has to be allowed there too, and so does the start of the next operand
+            let next = &self.constraint.positions[prefix_len + len];
+            let after_next = &self.constraint.positions[prefix_len + len + 1];
+            if (operands == 1 && !next.contains(&b'='))
+                || (operands > 1 && !OPERATOR_BYTES.iter().any(|op| next.contains(op)))
+                || (operands > 1 && !after_next.iter().any(|ch| ch.is_ascii_digit() && (*ch != b'0' || self.constraint.rules.zero_operands))) {
+                continue;
+            }
+            let mut range = range_intersect(&range_for_digits(len, self.constraint.rules.zero_operands), &self.digits_range(prefix_len, len));
+            if operands == 1 {
+                // Last operand, so we know how long the result is and can skip values that can't produce it
+                let c_range = range_intersect(&self.result_range(digits - len), &self.constraint.c_constraint.range);
+                if let Some(op) = last_op {
+                    if let Some(last_range) = last_operand_range(&self.prefix[..prefix_len - 1], op, &c_range) {
+                        range = range_intersect(&range, &last_range);
                     }
-                });
+                }
             }
-        }
-        let b_constraint = &ExpressionNumberConstraint::intersect(&b_base_constraint, &constraint.b_constraint);
-        // println!("Finding value for b");
-        let b = skip_fail!(find_num_with_constraint(&b_constraint), "Failed to generate b");
-        let b_len = skip_fail!(b.len(), "b does not have valid length");
-        // println!("Generated b {} from constraint: {}", b, &b_constraint);
-
-        remaining_chars -= b_len;
-
-        parts.push(ExpressionPart::Number(a));
-        parts.push(op2op(&op1));
-        parts.push(ExpressionPart::Number(b));
-
-        match op2_opt {
-            Some(op2) => {
-                parts.push(op2op(&op2));
-                let b2_remaining_chars = remaining_chars
-                    - 1 // op2
-                    - 2 // =c
-                ;
-
-                let range = range_for_digits_or_less(b2_remaining_chars, false);
-                let b2_base_constraint = ExpressionNumberConstraint {
-                    description: format!("{}..={} for {}-digit number", range.start(), range.end(), b_remaining_chars),
-                    range,
-                    ..Default::default()
+
+            for value in range {
+                if !self.push(&value.to_string()) {
+                    continue;
+                }
+                let keep_going = if operands == 1 {
+                    self.finish(visit)
+                } else {
+                    let mut keep_going = true;
+                    for op in OPERATOR_BYTES.iter() {
+                        if self.push(&(*op as char).to_string()) {
+                            if self.ops_possible(operands - 2) {
+                                keep_going = self.expr(Some(*op), operands - 1, digits - len, visit);
+                            }
+                            self.truncate(prefix_len + len);
+                        }
+                        if !keep_going {
+                            break;
+                        }
+                    }
+                    keep_going
                 };
-                // println!("Finding value for b2");
-                let b2 = find_num_with_constraint(&ExpressionNumberConstraint::intersect(&b2_base_constraint, &constraint.b2_constraint))?;
-                parts.push(ExpressionPart::Number(b2));
-            },
-            None => { }
+                self.truncate(prefix_len);
+                if !keep_going {
+                    return false;
+                }
+            }
         }
+        true
+    }
 
-        let expr = Expression { parts };
-        let res = skip_fail!(expr.calculate(), format!("Error calculating expression {}", expr));
-        // println!("Generating c from constraint: {}", &constraint.c_constraint);
-        match constraint.c_constraint.accept(&res) {
-            Err(_err) => {
-                // println!("c {} from expr {} did not match c_constraint {}: {}", &res, &expr, constraint.c_constraint, err);
-                continue;
-            },
-            Ok(()) => {
-                // println!("Accepted c={}", &res);
+    // Add part to prefix if the constraint allows its characters there and there's still room for everything
+    // we need more of.  If not, leave prefix as it was.
+    fn push(&mut self, part: &str) -> bool {
+        let prefix_len = self.prefix.len();
+        for ch in part.bytes() {
+            let pos = self.prefix.len();
+            let count = self.counts.entry(ch).or_insert(0);
+            *count += 1;
+            self.prefix.push(ch as char);
+            if pos >= self.constraint.len() || !self.constraint.positions[pos].contains(&ch) || *count > self.max_count(ch) {
+                self.truncate(prefix_len);
+                return false;
             }
         }
+        if !self.counts_possible() {
+            self.truncate(prefix_len);
+            return false;
+        }
+        true
+    }
 
-        let eq = Equation { expr, res };
-        match eq.len() {
-            Ok(len) => if len != NERDLE_CHARACTERS as usize {
-                // println!("Equation '{}' is wrong length ({} chars != {})", eq, eq.len(), NERDLE_CHARACTERS);
-                continue;
-            },
-            Err(_err) => {
-                // println!("Equation '{}' does not have valid length: {}", eq, err);
-                continue;
-
-            }
+    fn truncate(&mut self, len: usize) {
+        while self.prefix.len() > len {
+            let ch = self.prefix.pop().unwrap() as u8;
+            *self.counts.get_mut(&ch).unwrap() -= 1;
         }
-        if !eq.computes().unwrap_or(false) {
-            println!("Equation unexpectedly did not compute: {}", eq);
-            continue;
+    }
+
+    fn max_count(&self, ch: u8) -> u32 {
+        let chars = self.constraint.len() as u32;
+        let count = self.constraint.counts.get(&ch).map(|range| *range.end()).unwrap_or(chars);
+        let op = self.constraint.operator.get(&ch).map(|range| *range.end()).unwrap_or(chars);
+        min(count, op)
+    }
+
+    fn min_count(&self, ch: u8) -> u32 {
+        let count = self.constraint.counts.get(&ch).map(|range| *range.start()).unwrap_or(0);
+        let op = self.constraint.operator.get(&ch).map(|range| *range.start()).unwrap_or(0);
+        max(count, op)
+    }
+
+    // Whether there are enough positions left for every character we still need more of
+    fn counts_possible(&self) -> bool {
+        let remaining = (self.constraint.len() - self.prefix.len()) as u32;
+        let needed: u32 = self.limited.iter()
+            .map(|ch| self.min_count(*ch).saturating_sub(*self.counts.get(ch).unwrap_or(&0)))
+            .sum();
+        needed <= remaining
+    }
+
+    // The smallest and largest numbers of this many digits, starting at this position, that the constraint
+    // allows there.  The range is empty if some position can't be a digit at all.
+    fn digits_range(&self, start: usize, digits: usize) -> RangeInclusive<i64> {
+        let mut low: i64 = 0;
+        let mut high: i64 = 0;
+        for pos in start..start + digits {
+            let allowed: Vec<i64> = self.constraint.positions[pos].iter()
+                .filter(|ch| ch.is_ascii_digit() && (**ch != b'0' || pos > start || digits == 1))
+                .map(|ch| (ch - b'0') as i64)
+                .collect();
+            low = low.saturating_mul(10).saturating_add(*allowed.iter().min().unwrap_or(&9));
+            high = high.saturating_mul(10).saturating_add(*allowed.iter().max().unwrap_or(&-1));
         }
+        low..=high
+    }
 
-        match constraint.accept(&eq) {
-            Err(_err) => {
-                // println!("Equation {} did not match constraint: {}", eq, err);
-                continue;
-            },
-            Ok(()) => { }
+    // Every result which could take up the last c_digits characters, including a minus sign if the rules allow
+    // negative results.  Positive and negative results are covered by one range, so it may be too wide.
+    fn result_range(&self, c_digits: usize) -> RangeInclusive<i64> {
+        let rules = &self.constraint.rules;
+        let start = self.constraint.len() - c_digits;
+        let positive = range_intersect(&range_for_digits(c_digits, rules.zero_results), &self.digits_range(start, c_digits));
+        if !rules.negative_results || c_digits < 2 || !self.constraint.positions[start].contains(&b'-') {
+            return positive;
         }
+        let negative = range_intersect(&range_for_digits(c_digits - 1, false), &self.digits_range(start + 1, c_digits - 1));
+        if negative.is_empty() {
+            positive
+        } else if positive.is_empty() {
+            -negative.end()..=-negative.start()
+        } else {
+            -negative.end()..=*positive.end()
+        }
+    }
 
-        return Ok(eq);
+    // Whether the operators we still need more of fit in the operator positions left
+    fn ops_possible(&self, ops_left: usize) -> bool {
+        let needed: u32 = OPERATOR_BYTES.iter()
+            .map(|op| self.min_count(*op).saturating_sub(*self.counts.get(op).unwrap_or(&0)))
+            .sum();
+        needed as usize <= ops_left
     }
 
-    Err(NoMatchFound { message: format!("Failed to generate equation after {} attempts", ATTEMPTS) })
+    // All the operands are in place, so the result is whatever the expression comes to
+    fn finish(&mut self, visit: &mut dyn FnMut(Equation) -> bool) -> bool {
+        let expr_len = self.prefix.len();
+        let expr = match Expression::from_str(&self.prefix) {
+            Ok(expr) => expr,
+            Err(_) => return true,
+        };
+        let res = match expr.calculate() {
+            Ok(res) => res,
+            Err(_) => return true,
+        };
+        let keep_going = match res.int_value() {
+            Ok(value) if self.push(&format!("={}", value)) && self.prefix.len() == self.constraint.len() => {
+                let eq = Equation { expr, res };
+                self.constraint.accept(&eq).is_err() || visit(eq)
+            },
+            _ => true,
+        };
+        self.truncate(expr_len);
+        keep_going
+    }
 }
 
-fn op2op (op: &ExpressionOperatorEnum) -> ExpressionPart {
-    let op: Box<dyn ExpressionOperator> = match &op {
-        ExpressionOperatorEnum::Plus => Box::new(ExpressionOperatorPlus { }),
-        ExpressionOperatorEnum::Minus => Box::new(ExpressionOperatorMinus { }),
-        ExpressionOperatorEnum::Times => Box::new(ExpressionOperatorTimes { }),
-        ExpressionOperatorEnum::Divide => Box::new(ExpressionOperatorDivide { }),
-    };
-    ExpressionPart::Operator(op)
+// If everything before the last operator can be calculated on its own, work out which values of the last operand
+// could give a result in c_range.  Returns None if we can't tell.
+fn last_operand_range(prefix: &str, op: u8, c_range: &RangeInclusive<i64>) -> Option<RangeInclusive<i64>> {
+    let prefix = Expression::from_str(prefix).ok()?;
+    let op_precedence = ExpressionPart::from_char_byte(&op).ok().and_then(|part| match part {
+        ExpressionPart::Operator(op) => Some(op.precedence()),
+        _ => None,
+    })?;
+    // A lower-precedence operator earlier in the prefix would be applied after this one
+    let closed = prefix.parts.iter().all(|part| match part {
+        ExpressionPart::Operator(prefix_op) => prefix_op.precedence() <= op_precedence,
+        _ => true,
+    });
+    if !closed {
+        return None;
+    }
+    let p = prefix.calculate().ok()?.int_value().ok()?;
+    let (c_min, c_max) = (*c_range.start(), *c_range.end());
+    match op {
+        b'+' => Some(c_min.checked_sub(p)?..=c_max.checked_sub(p)?),
+        b'-' => Some(p.checked_sub(c_max)?..=p.checked_sub(c_min)?),
+        b'*' if p > 0 => Some((c_min.saturating_add(p - 1) / p)..=(c_max / p)),
+        b'/' if p > 0 && c_max > 0 => Some((p / c_max.saturating_add(1) + 1)..=(if c_min > 0 { p / c_min } else { p })),
+        _ => None,
+    }
 }
 
-pub fn eqgen() -> Result<Equation, NoMatchFound> {
-    eqgen_constrained(&EquationConstraint::default())
+#[cfg(test)]
+use crate::nerdle::NERDLE_CHARACTERS;
+
+#[test]
+fn eqgen_all_test() {
+    let all = eqgen_all();
+    let strs: Vec<String> = all.iter().map(|eq| eq.to_string()).collect();
+    // The well-known number of valid 8-character Nerdle equations
+    assert_eq!(17723, all.len());
+    assert!(strs.contains(&"12+34=46".to_string()));
+    assert!(!strs.contains(&"1+2*3=7".to_string()));
+    assert!(strs.contains(&"9+8/2=13".to_string()));
+    assert!(strs.contains(&"100/2=50".to_string()));
+    for eq in all.iter() {
+        assert_eq!(eq.len().unwrap(), NERDLE_CHARACTERS as usize);
+        assert!(eq.computes().unwrap());
+    }
+}
+
+#[test]
+fn eqgen_search_test() {
+    // The search finds the same equations as checking every one against the constraint
+    let mut constraint = EquationConstraint {
+        counts: HashMap::from([(b'+', 1..=1), (b'3', 1..=2)]),
+        ..Default::default()
+    };
+    constraint.positions[1].remove(&b'5');
+    let mut found = Vec::new();
+    eqgen_search(&constraint, &mut |eq| {
+        found.push(eq.to_string());
+        true
+    });
+    let mut expected: Vec<String> = eqgen_all().iter()
+        .filter(|eq| constraint.accept(eq).is_ok())
+        .map(|eq| eq.to_string())
+        .collect();
+    found.sort();
+    expected.sort();
+    assert!(!found.is_empty());
+    assert_eq!(found, expected);
 }
 
-fn gen_ops(constraint: &EquationConstraint) -> Result<(ExpressionOperatorEnum, Option<ExpressionOperatorEnum>), NoMatchFound> {
-    let mut tries = 0;
-    return Ok(loop {
-        tries += 1;
-        if tries > ATTEMPTS {
-            return Err(NoMatchFound { message: format!("Could not find operator after {} tries for constraint {}", tries, &constraint)})
+#[test]
+fn eqgen_constrained_test() {
+    // Everything but the operator and the first digit of the result is known
+    let mut constraint = EquationConstraint::default();
+    for (pos, ch) in "12?34=?6".bytes().enumerate() {
+        if ch != b'?' {
+            constraint.positions[pos] = HashSet::from([ch]);
         }
+    }
+    assert_eq!(eqgen_constrained(&constraint).unwrap().to_string(), "12+34=46");
 
-        let num_ops = range_rand_or_only(constraint.num_ops.clone())?;
+    // Without + nothing fits, and the search can tell
+    constraint.positions[2].remove(&b'+');
+    assert!(eqgen_constrained(&constraint).is_err());
 
-        let (op1, op2_opt): (ExpressionOperatorEnum, Option<ExpressionOperatorEnum>) =
-            if num_ops == 1 {
-                ( rand::random(), None )
-            } else {
-                ( rand::random(), Some(rand::random()) )
-            }
-        ;
+    let constraint = EquationConstraint {
+        counts: HashMap::from([(b'1', 0..=0), (b'+', 2..=2)]),
+        ..Default::default()
+    };
+    for _ in 0..10 {
+        let eq = eqgen_constrained(&constraint).unwrap().to_string();
+        assert!(!eq.contains('1'));
+        assert_eq!(eq.matches('+').count(), 2);
+    }
+}
 
-        if are_ops_ok(&op1, &op2_opt, &constraint, tries) {
-            break (op1, op2_opt);
-        } else {
-            continue;
-        }
-    })
+#[test]
+fn eqgen_operator_count_test() {
+    // Every equation found has each operator the number of times the constraint asks for
+    let check = |operator: HashMap<u8, RangeInclusive<u32>>| {
+        let constraint = EquationConstraint { operator: operator.clone(), ..Default::default() };
+        let mut found = 0;
+        eqgen_search(&constraint, &mut |eq| {
+            let eq = eq.to_string();
+            for (op, range) in operator.iter() {
+                assert!(range.contains(&(eq.bytes().filter(|ch| ch == op).count() as u32)), "{} breaks {}", eq, constraint);
+            }
+            found += 1;
+            true
+        });
+        found
+    };
+    for op in OPERATOR_BYTES.iter() {
+        assert!(check(HashMap::from([(*op, 1..=1)])) > 0);
+        assert!(check(HashMap::from([(*op, 2..=2)])) > 0);
+    }
+    // One operator required while another is ruled out
+    assert!(check(HashMap::from([(b'-', 1..=1), (b'+', 0..=0)])) > 0);
+    // More operators required than there is room for
+    assert_eq!(check(HashMap::from([(b'+', 2..=2), (b'-', 1..=1)])), 0);
 }
 
-fn are_ops_ok(op1: &ExpressionOperatorEnum, op2_opt: &Option<ExpressionOperatorEnum>, constraint: &EquationConstraint, _tries: u32) -> bool {
-    let mut op_count: HashMap<u8, u32> = HashMap::new();
-    op_count.insert(op1.to_char_byte(), 1);
-    match &op2_opt {
-        Some(op2) => {
-            let count = op_count.entry(op2.to_char_byte()).or_insert(0);
-            *count += 1;
-        },
-        None => { }
+#[cfg(test)]
+fn eqgen_test_sample(constraint: &EquationConstraint, samples: usize, weight: &dyn Fn(&Equation) -> f64) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for _ in 0..samples {
+        *counts.entry(eqgen_weighted(constraint, weight).unwrap().to_string()).or_insert(0) += 1;
     }
+    counts
+}
 
-    // let op2_str = || match &op2_opt {
-    //     Some(op2) => op2.to_string(),
-    //     None => String::from("None")
-    // };
-
-    for (op, info) in constraint.operator.iter() {
-        match op_count.get(op) {
-            None => {
-                if info.start() >= &1 {
-                    // println!("Rejected case 1 operators ({}, {}) on try {}: operator {} should appear at least {} times but appeared {}, constraint: {}",
-                    //     &op1, op2_str(), tries,
-                    //     *op as char, info.start(), "none",
-                    //     &constraint);
-                    return false
-                } else {
-                    // println!("Passed case 1 operators ({}, {}) on try {}: operator {} should appear at least {} times and appeared {}, constraint: {}",
-                    //     &op1, op2_str(), tries,
-                    //     *op as char, info.start(), "none",
-                    //     &constraint);
-                }
-            },
-            Some(count) => {
-                if count < info.start() || count > info.end() {
-                    // println!("Rejected case 2 operators ({}, {}) on try {}: operator {} should appear between {} and {} times and appeared {}, constraint {}",
-                    //     &op1, op2_str(), tries,
-                    //     *op as char, info.start(), info.end(), count,
-                    //     &constraint);
-                    return false;
-                } else {
-                    // println!("Passed case 2 operators ({}, {}) on try {}: operator {} should appear between {} and {} times and appeared {}, constraint {}",
-                    //     &op1, op2_str(), tries,
-                    //     *op as char, info.start(), info.end(), count,
-                    //     &constraint);
-                }
-            }
+#[cfg(test)]
+fn eqgen_test_constraint() -> EquationConstraint {
+    // 14 equations, from 3*35=105 to 99+6=105
+    let mut constraint = EquationConstraint::default();
+    for (pos, ch) in "????=105".bytes().enumerate() {
+        if ch != b'?' {
+            constraint.positions[pos] = HashSet::from([ch]);
         }
     }
+    constraint
+}
+
+#[test]
+fn eqgen_uniform_test() {
+    let constraint = eqgen_test_constraint();
+    let samples = 700;
+    let mut counts = HashMap::new();
+    for _ in 0..samples {
+        *counts.entry(eqgen_constrained(&constraint).unwrap().to_string()).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 14);
 
-    // println!("Accepted operators ({}, {}) on try {} from constraint: {}", &op1, op2_str(), tries, &constraint);
-    true
+    // Chi-squared with 13 degrees of freedom is above 40 by chance less than once in ten thousand runs
+    let expected = samples as f64 / counts.len() as f64;
+    let chi_squared: f64 = counts.values().map(|count| (*count as f64 - expected).powi(2) / expected).sum();
+    assert!(chi_squared < 40.0, "chi-squared {} for {:?}", chi_squared, counts);
 }
 
-#[cfg(test)]
 #[test]
-fn are_ops_ok_test() {
-    // Simple cases with no constraints
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &None,
-        &EquationConstraint::default(),
-        1
-    ));
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Minus,
-        &None,
-        &EquationConstraint::default(),
-        1
-    ));
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Divide,
-        &None,
-        &EquationConstraint::default(),
-        1
-    ));
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Times,
-        &None,
-        &EquationConstraint::default(),
-        1
-    ));
-
-
-    // Simple cases where operators meet constraints
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &None,
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('+' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Minus,
-        &None,
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Divide,
-        &None,
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('/' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Times,
-        &None,
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('*' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    // Must appear once, and appears in second operator
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Minus,
-        &Some(ExpressionOperatorEnum::Plus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('+' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Divide),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('/' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Times),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('*' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    // Must appear twice, and does
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Plus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('+' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Minus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Divide,
-        &Some(ExpressionOperatorEnum::Divide),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('/' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(are_ops_ok(
-        &ExpressionOperatorEnum::Times,
-        &Some(ExpressionOperatorEnum::Times),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('*' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    // Operator does not appear but must
-    assert!(!are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &None,
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 1..=1),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    // Operator appears but must not
-    assert!(!are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('+' as u8, 0..=0),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(!are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 0..=0),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    // Operator appears once but must appear twice
-    assert!(!are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('-' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
-
-    assert!(!are_ops_ok(
-        &ExpressionOperatorEnum::Plus,
-        &Some(ExpressionOperatorEnum::Minus),
-        &EquationConstraint {
-            operator: HashMap::from([
-                ('+' as u8, 2..=2),
-            ]),
-            ..Default::default()
-        },
-        1
-    ));
+fn eqgen_weighted_test() {
+    let constraint = eqgen_test_constraint();
+    // 6 of the 13 with any weight multiply, so with double weight they make up 12 of every 19
+    let counts = eqgen_test_sample(&constraint, 1000, &|eq| {
+        let eq = eq.to_string();
+        if eq.contains('*') { 2.0 } else if eq.starts_with("99") { 0.0 } else { 1.0 }
+    });
+    assert!(!counts.contains_key("99+6=105"));
+    let times: usize = counts.iter().filter(|(eq, _)| eq.contains('*')).map(|(_, count)| count).sum();
+    let share = times as f64 / 1000.0;
+    assert!((share - 12.0 / 19.0).abs() < 0.05, "multiplication share {}", share);
+
+    assert!(eqgen_weighted(&constraint, &|_| 0.0).is_err());
+}
+
+#[test]
+fn eqgen_three_ops_test() {
+    let constraint = EquationConstraint::from_str("?+?*?-?=?").unwrap();
+    let found: Vec<String> = constraint.iter().map(|eq| eq.to_string()).collect();
+    assert!(found.contains(&"1+2*3-4=3".to_string()));
+    assert!(found.iter().all(|eq| eq.len() == 9));
+    assert!(constraint.accept(&Equation::from_str("1+2*3-4=3").unwrap()).is_ok());
+    assert!(constraint.accept(&Equation::from_str("12+34=46").unwrap()).is_err());
+
+    // Maxi Nerdle has 10 characters, enough for three operators with a two-digit result
+    let constraint = EquationConstraint {
+        num_ops: 3..=3,
+        ..EquationConstraint::for_length(10)
+    };
+    let mut first = None;
+    eqgen_search(&constraint, &mut |eq| {
+        first = Some(eq);
+        false
+    });
+    let eq = first.unwrap();
+    assert_eq!(eq.len().unwrap(), 10);
+    assert_eq!(eq.expr.parts.len(), 7);
+    assert!(eq.computes().unwrap());
+}
+
+#[test]
+fn eqgen_fraction_sum_test() {
+    let constraint = EquationConstraint::from_str("?/?+?/?=?").unwrap();
+    let found: Vec<String> = constraint.iter().map(|eq| eq.to_string()).collect();
+    assert!(found.contains(&"1/2+1/2=1".to_string()));
+    assert!(found.iter().all(|eq| Equation::from_str(eq).unwrap().computes().unwrap()));
+    let differences = EquationConstraint::from_str("?/?-?/?=?").unwrap();
+    assert!(differences.iter().any(|eq| eq.to_string() == "5/2-3/2=1"));
+
+    let constraint = EquationConstraint::from_str("?/?+?/?=?; rules no_fractions").unwrap();
+    assert!(!constraint.iter().any(|eq| eq.to_string() == "1/2+1/2=1"));
+}
+
+#[test]
+fn eqgen_rules_test() {
+    let found = |pattern: &str| -> Vec<String> {
+        EquationConstraint::from_str(pattern).unwrap().iter().map(|eq| eq.to_string()).collect()
+    };
+    assert!(found("0+??=??").is_empty());
+    assert_eq!(found("0+??=?? ; rules zero_operands").len(), 90);
+
+    assert!(found("??-??=-?").is_empty());
+    let negative = found("??-??=-? ; rules negative_results");
+    assert!(negative.contains(&"10-11=-1".to_string()));
+    assert_eq!(negative.len(), 765);
+
+    // The search only finds what checking every equation against the rules would
+    let constraint = EquationConstraint::from_str("rules no_zero_results,no_fractions,no_negative_intermediates").unwrap();
+    let expected = eqgen_all().iter().filter(|eq| constraint.rules.check(eq).is_ok()).count();
+    assert_eq!(constraint.count(), expected);
+    assert!(expected < 17723);
 }
@@ -0,0 +1,127 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::eq::Equation;
+use crate::nerdle::{nerdle_str, NerdleResult, NerdleError};
+
+// Scores a guess by how well it splits up the answers which are still possible, like WordleBot.  Each possible
+// answer would give the guess some result; answers giving the same result can't be told apart afterwards.
+pub struct GuessEvaluation {
+    pub guess: String,
+    pub candidates: usize,
+    // Average number of answers left after this guess, if every candidate is equally likely
+    pub expected_remaining: f64,
+    // Bits of information the result is expected to give
+    pub entropy: f64,
+    // Whether the guess is one of the candidates, so it might win outright
+    pub could_win: bool,
+}
+
+impl fmt::Display for GuessEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {:.1} of {} answers expected to remain, {:.2} bits{}",
+            self.guess, self.expected_remaining, self.candidates, self.entropy,
+            if self.could_win { ", could win" } else { "" })
+    }
+}
+
+pub fn evaluate_guess(guess: &str, candidates: &[String]) -> Result<GuessEvaluation, NerdleError> {
+    let mut buckets: HashMap<NerdleResult, usize> = HashMap::new();
+    for candidate in candidates.iter() {
+        *buckets.entry(nerdle_str(guess, candidate)?).or_default() += 1;
+    }
+    let total = candidates.len() as f64;
+    let expected_remaining = buckets.values().map(|count| (*count as f64).powi(2)).sum::<f64>() / total.max(1.0);
+    let entropy = buckets.values()
+        .map(|count| *count as f64 / total)
+        .map(|p| -p * p.log2())
+        .sum();
+    Ok(GuessEvaluation {
+        guess: guess.to_string(),
+        candidates: candidates.len(),
+        expected_remaining,
+        entropy,
+        could_win: candidates.iter().any(|candidate| candidate == guess),
+    })
+}
+
+// How the guess compares to the best one, as a percentage.  With only one answer left information is
+// worthless, so then it's all about whether the guess could win.
+pub fn skill(guess: &GuessEvaluation, best: &GuessEvaluation) -> f64 {
+    if best.entropy <= 0.0 {
+        return if guess.could_win || !best.could_win { 100.0 } else { 0.0 };
+    }
+    (100.0 * guess.entropy / best.entropy).min(100.0)
+}
+
+// The candidates which would have given this result for this guess
+pub fn filter_candidates(candidates: &[String], guess: &str, res: &NerdleResult, commutative: bool) -> Vec<String> {
+    let matching: Vec<String> = candidates.iter()
+        .filter(|candidate| nerdle_str(guess, candidate).map(|actual| &actual == res).unwrap_or(false))
+        .cloned()
+        .collect();
+    distinct_candidates(&matching, commutative)
+}
+
+// When commuted forms of the answer win they're really all the same answer, so only the first of each is kept,
+// or they'd be counted as several answers left
+pub fn distinct_candidates(candidates: &[String], commutative: bool) -> Vec<String> {
+    if !commutative {
+        return candidates.to_vec();
+    }
+    let mut seen = HashSet::new();
+    candidates.iter()
+        .filter(|candidate| Equation::from_str(candidate).map(|eq| seen.insert(eq.canonical())).unwrap_or(true))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn evaluate_guess_test() {
+    let candidates: Vec<String> = ["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108", "4+3+3=10", "48-32=16", "20+30=50"]
+        .iter().map(|eq| eq.to_string()).collect();
+    let eval = evaluate_guess("12+34=46", &candidates).unwrap();
+    assert_eq!(eval.candidates, 8);
+    assert!(eval.could_win);
+    assert!(eval.expected_remaining >= 1.0 && eval.expected_remaining <= 8.0);
+    assert!(eval.entropy > 0.0 && eval.entropy <= 3.0);
+
+    // A guess which splits every candidate apart is as good as it gets
+    let perfect: Vec<String> = ["12+34=46", "10+20=30", "43-21=22", "9*12=108"].iter().map(|eq| eq.to_string()).collect();
+    let eval = evaluate_guess("12+34=46", &perfect).unwrap();
+    assert_eq!(eval.expected_remaining, 1.0);
+    assert_eq!(eval.entropy, 2.0);
+}
+
+#[test]
+fn skill_test() {
+    let candidates: Vec<String> = ["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108"].iter().map(|eq| eq.to_string()).collect();
+    let good = evaluate_guess("12+34=46", &candidates).unwrap();
+    let bad = evaluate_guess("11+11=22", &candidates).unwrap();
+    assert_eq!(skill(&good, &good), 100.0);
+    assert!(skill(&bad, &good) < 100.0);
+
+    let last = vec!["20+30=50".to_string()];
+    let winner = evaluate_guess("20+30=50", &last).unwrap();
+    let loser = evaluate_guess("10+20=30", &last).unwrap();
+    assert_eq!(skill(&winner, &winner), 100.0);
+    assert_eq!(skill(&loser, &winner), 0.0);
+}
+
+#[test]
+fn filter_candidates_test() {
+    let candidates: Vec<String> = ["10+20=30", "20+30=50", "30+20=50", "20+40=60"].iter().map(|eq| eq.to_string()).collect();
+    let res = nerdle_str("10+20=30", "20+30=50").unwrap();
+    let left = filter_candidates(&candidates, "10+20=30", &res, false);
+    assert!(left.contains(&"20+30=50".to_string()));
+    assert!(!left.contains(&"10+20=30".to_string()));
+
+    // Commuted forms of one answer only count once when they'd win
+    let candidates: Vec<String> = ["20+30=50", "30+20=50", "20+40=60"].iter().map(|eq| eq.to_string()).collect();
+    let res = nerdle_str("9*9-7=74", "20+30=50").unwrap();
+    assert_eq!(filter_candidates(&candidates, "9*9-7=74", &res, false), vec!["20+30=50", "30+20=50"]);
+    assert_eq!(filter_candidates(&candidates, "9*9-7=74", &res, true), vec!["20+30=50"]);
+    assert_eq!(distinct_candidates(&candidates, false).len(), 3);
+    assert_eq!(distinct_candidates(&candidates, true), vec!["20+30=50", "20+40=60"]);
+}
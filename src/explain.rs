@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::eq::Equation;
+use crate::nerdle::{NerdleResult, NerdlePositionResult, NERDLE_CHARACTERS};
+
+// Every reason an equation can't be the answer, each tied back to the feedback that rules it out, so people
+// can see which result they overlooked.  Turns and positions are numbered from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImpossibleReason {
+    pub turn: Option<usize>,
+    pub position: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ImpossibleReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+fn color(res: NerdlePositionResult) -> &'static str {
+    match res {
+        NerdlePositionResult::Green => "green",
+        NerdlePositionResult::Yellow => "yellow",
+        NerdlePositionResult::Gray => "gray",
+    }
+}
+
+// Check an equation against each guess and result so far
+pub fn explain_impossible(history: &[(Equation, NerdleResult)], eq: &Equation) -> Vec<ImpossibleReason> {
+    let mut reasons = Vec::new();
+    let eq_str = eq.to_string();
+    let eq_bytes = eq_str.as_bytes();
+
+    match eq.len() {
+        Ok(len) if len == NERDLE_CHARACTERS as usize => { },
+        _ => {
+            reasons.push(ImpossibleReason { turn: None, position: None,
+                message: format!("{} is not {} characters long", eq, NERDLE_CHARACTERS) });
+            // Nothing below makes sense without every position
+            return reasons;
+        },
+    }
+    if !eq.computes().unwrap_or(false) {
+        reasons.push(ImpossibleReason { turn: None, position: None, message: format!("{} does not compute", eq) });
+    }
+
+    for (turn, (guess, row)) in history.iter().enumerate() {
+        let turn = turn + 1;
+        let guess_str = guess.to_string();
+        let guess_bytes = guess_str.as_bytes();
+
+        for pos in 0..NERDLE_CHARACTERS as usize {
+            let ch = guess_bytes[pos] as char;
+            let have = eq_bytes[pos] as char;
+            let res = row.positions[pos];
+            // A gray for a character which isn't in the answer at all is covered by the count check below
+            let in_answer = (0..NERDLE_CHARACTERS as usize)
+                .any(|other| guess_bytes[other] as char == ch && row.positions[other] != NerdlePositionResult::Gray);
+            let wrong = match res {
+                NerdlePositionResult::Green => have != ch,
+                NerdlePositionResult::Yellow => have == ch,
+                NerdlePositionResult::Gray => have == ch && in_answer,
+            };
+            if wrong {
+                let message = match res {
+                    NerdlePositionResult::Green => format!("turn {} position {} was green for {}, but {} has {} there", turn, pos + 1, ch, eq, have),
+                    _ => format!("turn {} position {} was {} for {}, so {} can't be there", turn, pos + 1, color(res), ch, ch),
+                };
+                reasons.push(ImpossibleReason { turn: Some(turn), position: Some(pos + 1), message });
+            }
+        }
+
+        // Counts: green and yellow squares say how many there are at least, and a gray square says that's all
+        let mut checked = Vec::new();
+        for &ch in guess_bytes.iter() {
+            if checked.contains(&ch) {
+                continue;
+            }
+            checked.push(ch);
+            let found: Vec<usize> = (0..NERDLE_CHARACTERS as usize)
+                .filter(|pos| guess_bytes[*pos] == ch && row.positions[*pos] != NerdlePositionResult::Gray)
+                .collect();
+            let gray = (0..NERDLE_CHARACTERS as usize)
+                .find(|pos| guess_bytes[*pos] == ch && row.positions[*pos] == NerdlePositionResult::Gray);
+            let have = eq_bytes.iter().filter(|b| **b == ch).count();
+            let ch = ch as char;
+
+            if have < found.len() {
+                let positions: Vec<String> = found.iter().map(|pos| (pos + 1).to_string()).collect();
+                reasons.push(ImpossibleReason { turn: Some(turn), position: Some(found[0] + 1),
+                    message: format!("turn {} showed at least {} of {} (position {}), but {} has {}",
+                        turn, found.len(), ch, positions.join(", "), eq, have) });
+            }
+            if let Some(gray) = gray {
+                if have > found.len() {
+                    reasons.push(ImpossibleReason { turn: Some(turn), position: Some(gray + 1),
+                        message: match found.len() {
+                            0 => format!("turn {} position {} was gray for {}, so there is no {}, but {} has {}", turn, gray + 1, ch, ch, eq, have),
+                            n => format!("turn {} position {} was gray for {}, so there are only {} {}, but {} has {}", turn, gray + 1, ch, n, ch, eq, have),
+                        } });
+                }
+            }
+        }
+    }
+    reasons
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[cfg(test)]
+fn explain_test_history() -> Vec<(Equation, NerdleResult)> {
+    // Answer is 20+30=50
+    vec![(Equation::from_str("10+20=30").unwrap(), NerdleResult::from_str("-GGYGGYG").unwrap())]
+}
+
+#[test]
+fn explain_possible_test() {
+    let history = explain_test_history();
+    assert!(explain_impossible(&history, &Equation::from_str("20+30=50").unwrap()).is_empty());
+}
+
+#[test]
+fn explain_impossible_test() {
+    let history = explain_test_history();
+    let reasons: Vec<String> = explain_impossible(&history, &Equation::from_str("17+23=40").unwrap())
+        .iter().map(|reason| reason.to_string()).collect();
+    assert_eq!(reasons, vec![
+        "turn 1 position 2 was green for 0, but 17+23=40 has 7 there",
+        "turn 1 position 4 was yellow for 2, so 2 can't be there",
+        "turn 1 position 5 was green for 0, but 17+23=40 has 3 there",
+        "turn 1 position 1 was gray for 1, so there is no 1, but 17+23=40 has 1",
+        "turn 1 showed at least 3 of 0 (position 2, 5, 8), but 17+23=40 has 1",
+    ]);
+}
+
+#[test]
+fn explain_not_computing_test() {
+    let reasons = explain_impossible(&[], &Equation::from_str("20+30=51").unwrap());
+    assert_eq!(reasons.len(), 1);
+    assert_eq!(reasons[0].turn, None);
+    assert_eq!(explain_impossible(&[], &Equation::from_str("1+2=3").unwrap()).len(), 1);
+}
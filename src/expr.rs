@@ -28,6 +28,7 @@ enum ExpressionCalculateState {
     ExpectNumber,
 }
 
+#[derive(Clone)]
 pub struct Expression {
     pub parts: Vec<ExpressionPart>,
 }
@@ -117,6 +118,57 @@ impl Expression {
         Ok(())
     }
 
+    // A form of this expression which is the same for any reordering of terms that are added or multiplied, so
+    // 20+30 and 30+20 have the same canonical form, as do 2*3-4 and 3*2-4.  Terms which are added come first in
+    // sorted order, then terms which are subtracted.  Within a term, factors which are multiplied come first, then
+    // factors which are divided by.
+    pub fn canonical(&self) -> String {
+        let mut plus_terms: Vec<String> = Vec::new();
+        let mut minus_terms: Vec<String> = Vec::new();
+        let mut times_factors: Vec<String> = Vec::new();
+        let mut divide_factors: Vec<String> = Vec::new();
+        let mut term_op = '+';
+        let mut factor_op = '*';
+
+        // Simulate a + on the end so the last term is finished off
+        let end = ExpressionPart::Operator(Box::new(ExpressionOperatorPlus { }));
+        for part in self.parts.iter().chain(std::iter::once(&end)) {
+            match part {
+                ExpressionPart::Number(num) => match factor_op {
+                    '/' => divide_factors.push(num.to_string()),
+                    _ => times_factors.push(num.to_string()),
+                },
+                ExpressionPart::Operator(op) if op.precedence() == 0 => factor_op = op.as_char(),
+                ExpressionPart::Operator(op) => {
+                    times_factors.sort();
+                    divide_factors.sort();
+                    let mut term = times_factors.join("*");
+                    for factor in divide_factors.iter() {
+                        term.push('/');
+                        term.push_str(factor);
+                    }
+                    match term_op {
+                        '-' => minus_terms.push(term),
+                        _ => plus_terms.push(term),
+                    }
+                    times_factors.clear();
+                    divide_factors.clear();
+                    term_op = op.as_char();
+                    factor_op = '*';
+                },
+            }
+        }
+
+        plus_terms.sort();
+        minus_terms.sort();
+        let mut canonical = plus_terms.join("+");
+        for term in minus_terms.iter() {
+            canonical.push('-');
+            canonical.push_str(term);
+        }
+        canonical
+    }
+
     pub fn len(&self) -> Result<usize, InvalidExpressionError> {
         self.parts.iter().fold(Ok(0), |sum, part| -> Result<usize, InvalidExpressionError> {
             match sum {
@@ -143,7 +195,7 @@ impl fmt::Display for Expression {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum ExpressionPart {
     Number(ExpressionNumber),
     Operator(Box<dyn ExpressionOperator>),
@@ -180,8 +232,8 @@ impl ExpressionPart {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ExpressionNumber {
-     numerator: i32,
-     denominator: i32,
+     numerator: i64,
+     denominator: i64,
 }
 
 impl fmt::Display for ExpressionNumber {
@@ -204,7 +256,7 @@ impl Default for ExpressionNumber {
 }
 
 impl ExpressionNumber {
-    pub fn new(value: i32) -> ExpressionNumber {
+    pub fn new(value: i64) -> ExpressionNumber {
         ExpressionNumber {
             numerator: value,
             ..Default::default()
@@ -220,7 +272,7 @@ impl ExpressionNumber {
     }
 
     // TODO: Not really a great error type
-    pub fn int_value(&self) -> Result<i32, InvalidExpressionError> {
+    pub fn int_value(&self) -> Result<i64, InvalidExpressionError> {
         if self.is_int() {
             Ok(self.numerator)
         } else {
@@ -232,17 +284,62 @@ impl ExpressionNumber {
         self.denominator == 1
     }
 
+    pub fn is_negative(&self) -> bool {
+        (self.numerator < 0) != (self.denominator < 0)
+    }
+
+    // Add sign times other, or None on overflow.  Both fractions are put over their least common denominator.
+    fn plus(&self, other: &ExpressionNumber, sign: i64) -> Option<ExpressionNumber> {
+        let divisor = gcd(self.denominator, other.denominator).max(1);
+        let self_scale = other.denominator / divisor;
+        let other_scale = self.denominator / divisor;
+        let numerator = self.numerator.checked_mul(self_scale)?
+            .checked_add(other.numerator.checked_mul(other_scale)?.checked_mul(sign)?)?;
+        Some(ExpressionNumber {
+            numerator,
+            denominator: self.denominator.checked_mul(self_scale)?,
+        }.simplify())
+    }
+
+    // Multiply by numerator/denominator, or None on overflow.  Common factors are cancelled across the two
+    // fractions first, so products of fractions in lowest terms only overflow if the answer itself would.
+    fn times(&self, numerator: i64, denominator: i64) -> Option<ExpressionNumber> {
+        let first = gcd(self.numerator, denominator).max(1);
+        let second = gcd(numerator, self.denominator).max(1);
+        Some(ExpressionNumber {
+            numerator: (self.numerator / first).checked_mul(numerator / second)?,
+            denominator: (self.denominator / second).checked_mul(denominator / first)?,
+        }.simplify())
+    }
+
+    // Reduce to lowest terms with a positive denominator, so equal values always compare equal and an integer
+    // always has a denominator of 1
     pub fn simplify(self) -> ExpressionNumber {
-        if (self.numerator % self.denominator) == 0 {
-            ExpressionNumber::new(self.numerator / self.denominator)
-        } else {
-            // Cannot be simplified to int, just leave as-is.
-            self
+        let divisor = gcd(self.numerator, self.denominator);
+        if divisor == 0 {
+            // Only 0/0 gets here; leave it for whoever divided by zero to notice
+            return self;
+        }
+        let sign = if self.denominator < 0 { -1 } else { 1 };
+        ExpressionNumber {
+            numerator: sign * (self.numerator / divisor),
+            denominator: sign * (self.denominator / divisor),
         }
     }
 
 }
 
+// Greatest common divisor, always non-negative
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    a
+}
+
 // TODO: This should be merged into ExpressionOperator, possibly replace it
 #[derive(Debug)]
 pub enum ExpressionOperatorEnum {
@@ -354,13 +451,7 @@ impl ExpressionOperator for ExpressionOperatorPlus {
     }
 
     fn operate(&self, a: &ExpressionNumber, b: &ExpressionNumber) -> Result<ExpressionNumber, InvalidExpressionError> {
-        match (a.int_value(), b.int_value()) {
-            (Ok(a_value), Ok(b_value)) => {
-                let value = a_value.checked_add(b_value).ok_or(InvalidExpressionError { message: format!("Could not compute {} + {}", a, b)} )?;
-                Ok(ExpressionNumber::new(value))
-            }
-            (_, _) => Err(InvalidExpressionError { message: format!("Could not add {} + {}: one or both values are not integers", a, b)})
-        }
+        a.plus(b, 1).ok_or(InvalidExpressionError { message: format!("Could not compute {} + {}", a, b)} )
     }
 }
 
@@ -379,13 +470,7 @@ impl ExpressionOperator for ExpressionOperatorMinus {
     }
 
     fn operate(&self, a: &ExpressionNumber, b: &ExpressionNumber) -> Result<ExpressionNumber, InvalidExpressionError> {
-        match (a.int_value(), b.int_value()) {
-            (Ok(a_value), Ok(b_value)) => {
-                let value = a_value.checked_sub(b_value).ok_or(InvalidExpressionError { message: format!("Could not compute {} - {}", a, b)} )?;
-                Ok(ExpressionNumber::new(value))
-            }
-            (_, _) => Err(InvalidExpressionError { message: format!("Could not add {} + {}: one or both values are not integers", a, b)})
-        }
+        a.plus(b, -1).ok_or(InvalidExpressionError { message: format!("Could not compute {} - {}", a, b)} )
     }
 }
 
@@ -403,12 +488,7 @@ impl ExpressionOperator for ExpressionOperatorTimes {
     }
 
     fn operate(&self, a: &ExpressionNumber, b: &ExpressionNumber) -> Result<ExpressionNumber, InvalidExpressionError> {
-        let numerator = a.numerator.checked_mul(b.numerator).ok_or(InvalidExpressionError { message: format!("Could not compute numerator for {} * {}", a, b)} )?;
-        let denominator = a.denominator.checked_mul(b.denominator).ok_or(InvalidExpressionError { message: format!("Could not compute numerator for {} * {}", a, b)} )?;
-        Ok(ExpressionNumber {
-            numerator,
-            denominator,
-        }.simplify())
+        a.times(b.numerator, b.denominator).ok_or(InvalidExpressionError { message: format!("Could not compute {} * {}", a, b)} )
     }
 }
 
@@ -428,13 +508,11 @@ impl ExpressionOperator for ExpressionOperatorDivide {
 
 
     fn operate(&self, a: &ExpressionNumber, b: &ExpressionNumber) -> Result<ExpressionNumber, InvalidExpressionError> {
+        if b.numerator == 0 {
+            return Err(InvalidExpressionError { message: format!("Could not compute {} / {}: division by zero", a, b)});
+        }
         // flip numerator and denominator of b
-        let numerator = a.numerator.checked_mul(b.denominator).ok_or(InvalidExpressionError { message: format!("Could not compute numerator for {} * {}", a, b)} )?;
-        let denominator = a.denominator.checked_mul(b.numerator).ok_or(InvalidExpressionError { message: format!("Could not compute numerator for {} * {}", a, b)} )?;
-        Ok(ExpressionNumber {
-            numerator,
-            denominator,
-        }.simplify())
+        a.times(b.denominator, b.numerator).ok_or(InvalidExpressionError { message: format!("Could not compute {} / {}", a, b)} )
     }
 }
 
@@ -444,7 +522,7 @@ impl FromStr for Expression {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         let mut parts: Vec<ExpressionPart> = Vec::new();
         let mut in_num: bool = false;
-        let mut accum: i32 = 0;
+        let mut accum: i64 = 0;
 
         // Simulate an extra space on the end so we get the last number
         let iter = input.as_bytes().iter().chain(" ".as_bytes().iter());
@@ -452,8 +530,9 @@ impl FromStr for Expression {
             match item {
                 b'0'..=b'9' => {
                     in_num = true;
-                    accum *= 10;
-                    accum += (item - b'0') as i32;
+                    accum = accum.checked_mul(10)
+                        .and_then(|accum| accum.checked_add((item - b'0') as i64))
+                        .ok_or(InvalidExpressionError { message: format!("Number in '{}' is too large", input.trim()) })?;
                 },
                 _ => {
                     if in_num {
@@ -475,14 +554,6 @@ impl FromStr for Expression {
     }
 }
 
-
-pub fn mknum(x:i32) -> ExpressionNumber {
-    ExpressionNumber::new(x)
-}
-
-// pub fn mknump(x:i32) -> ExpressionPart {
-//     ExpressionPart::Number(mknum(x))
-// }
 #[cfg(test)]
 #[test]
 fn simple_int_test() {
@@ -552,6 +623,46 @@ fn simplify_test() {
     }
 }
 
+#[test]
+fn simplify_frac_test() {
+    let a = ExpressionNumber {
+        numerator: 6,
+        denominator: -4,
+    }.simplify();
+    assert_eq!(a.numerator, -3);
+    assert_eq!(a.denominator, 2);
+    assert!(a.is_negative());
+    assert_eq!(a.to_string(), "-3/2");
+
+    let b = ExpressionNumber {
+        numerator: 0,
+        denominator: 7,
+    }.simplify();
+    assert_eq!(b, ExpressionNumber::new(0));
+}
+
+#[test]
+fn wide_number_test() {
+    let expr = Expression::from_str("123456789012*10").unwrap();
+    assert_eq!(expr.calculate().unwrap().int_value().unwrap(), 1234567890120);
+    assert_eq!(ExpressionNumber::new(1234567890120).len().unwrap(), 13);
+    assert!(Expression::from_str("99999999999999999999+1").is_err());
+    assert!(Expression::from_str("9999999999*9999999999").unwrap().calculate().is_err());
+
+    // Fractions whose plain products would overflow are cancelled down first
+    let times = ExpressionOperatorTimes { };
+    let a = ExpressionNumber {
+        numerator: 4000000007,
+        denominator: 4000000009,
+    };
+    let b = ExpressionNumber {
+        numerator: 4000000009,
+        denominator: 4000000007,
+    };
+    assert_eq!(times.operate(&a, &b).unwrap(), ExpressionNumber::new(1));
+    assert!(ExpressionOperatorDivide { }.operate(&a, &ExpressionNumber::new(0)).is_err());
+}
+
 #[test]
 fn add_int_test() {
     let plus = ExpressionOperatorPlus { };
@@ -572,7 +683,22 @@ fn add_frac_test() {
             numerator: 1,
             denominator: 2,
         };
-        assert!(plus.operate(&a, &b).is_err());
+        let c = plus.operate(&a, &b).unwrap();
+        assert_eq!(c.to_string(), "21/2");
+        let d = plus.operate(&c, &b).unwrap();
+        assert_eq!(d.int_value().unwrap(), 11);
+    }
+
+    {
+        let a = ExpressionNumber {
+            numerator: 1,
+            denominator: 6,
+        };
+        let b = ExpressionNumber {
+            numerator: 1,
+            denominator: 3,
+        };
+        assert_eq!(plus.operate(&a, &b).unwrap().to_string(), "1/2");
     }
 }
 
@@ -596,7 +722,22 @@ fn sub_frac_test() {
             numerator: 1,
             denominator: 2,
         };
-        assert!(minus.operate(&a, &b).is_err());
+        let c = minus.operate(&a, &b).unwrap();
+        assert_eq!(c.to_string(), "19/2");
+        assert_eq!(minus.operate(&b, &a).unwrap().to_string(), "-19/2");
+    }
+
+    {
+        let a = ExpressionNumber {
+            numerator: 3,
+            denominator: 4,
+        };
+        let b = ExpressionNumber {
+            numerator: 1,
+            denominator: 4,
+        };
+        assert_eq!(minus.operate(&a, &b).unwrap().to_string(), "1/2");
+        assert!(minus.operate(&a, &a).unwrap().is_int());
     }
 }
 
@@ -667,3 +808,18 @@ fn div_frac_test() {
         assert_eq!(d.int_value().unwrap(), 2);
     }
 }
+
+#[test]
+fn canonical_test() {
+    let canonical = |expr: &str| Expression::from_str(expr).unwrap().canonical();
+    assert_eq!(canonical("20+30"), canonical("30+20"));
+    assert_eq!(canonical("2*3-4"), canonical("3*2-4"));
+    assert_eq!(canonical("10-2+3"), canonical("3+10-2"));
+    assert_eq!(canonical("8/2*3"), canonical("3*8/2"));
+    assert_eq!(canonical("1+2*3"), canonical("3*2+1"));
+
+    // Subtraction and division don't commute, and precedence must be respected
+    assert_ne!(canonical("30-20"), canonical("20-30"));
+    assert_ne!(canonical("8/2"), canonical("2/8"));
+    assert_ne!(canonical("1+2*3"), canonical("2+1*3"));
+}
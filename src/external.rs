@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+
+use crate::bot::{BotCommand, BotResponse};
+use crate::eq::Equation;
+use crate::constraint::NoMatchFound;
+use crate::host::GameMode;
+use crate::nerdle::{NerdleResult, NerdleError};
+use crate::strategy::Strategy;
+use crate::explain::ImpossibleReason;
+use crate::rules::RuleSet;
+
+// A strategy implemented by another program, which we talk to with the same line protocol as the `bot`
+// command (see bot.rs).  The program gets one NEWGAME when it starts, then GUESS? and RESULT for each turn,
+// and QUIT when we're done with it.  Anything it writes to stderr is passed through.
+pub struct ExternalStrategy {
+    command: String,
+    process: RefCell<ExternalProcess>,
+}
+
+struct ExternalProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ExternalProcess {
+    fn send(&mut self, command: &BotCommand) -> Result<BotResponse, NerdleError> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| NerdleError { message: format!("Failed to send '{}' to external strategy: {}", command, err)})?;
+        let mut line = String::new();
+        let read = self.stdout.read_line(&mut line)
+            .map_err(|err| NerdleError { message: format!("Failed to read from external strategy: {}", err)})?;
+        if read == 0 {
+            return Err(NerdleError { message: format!("External strategy exited after '{}'", command)});
+        }
+        BotResponse::from_str(&line)
+    }
+}
+
+impl ExternalStrategy {
+    // The command is split on whitespace into the program and its arguments
+    pub fn new(command: &str) -> Result<ExternalStrategy, NerdleError> {
+        let mut words = command.split_whitespace();
+        let program = words.next()
+            .ok_or_else(|| NerdleError { message: "Empty external strategy command".to_string()})?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| NerdleError { message: format!("Failed to start external strategy '{}': {}", command, err)})?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let strategy = ExternalStrategy {
+            command: command.to_string(),
+            process: RefCell::new(ExternalProcess { child, stdin, stdout }),
+        };
+        strategy.new_game(GameMode::Classic)?;
+        Ok(strategy)
+    }
+
+    fn send(&self, command: &BotCommand) -> Result<BotResponse, NerdleError> {
+        self.process.borrow_mut().send(command)
+    }
+
+    fn new_game(&self, mode: GameMode) -> Result<(), NerdleError> {
+        match self.send(&BotCommand::NewGame(mode))? {
+            BotResponse::Ok => Ok(()),
+            response => Err(NerdleError { message: format!("Unexpected response to NEWGAME: {}", response)}),
+        }
+    }
+}
+
+impl Strategy for ExternalStrategy {
+    fn take_guess(&self) -> Result<Equation, NoMatchFound> {
+        match self.send(&BotCommand::Guess) {
+            Ok(BotResponse::Guess(guess)) => Equation::from_str(&guess)
+                .map_err(|err| NoMatchFound { message: format!("External strategy guessed invalid equation '{}': {}", guess, err)}),
+            Ok(response) => Err(NoMatchFound { message: format!("External strategy did not guess: {}", response)}),
+            Err(err) => Err(NoMatchFound { message: err.message }),
+        }
+    }
+
+    fn update(&mut self, guess: &Equation, result: &NerdleResult) {
+        match self.send(&BotCommand::Result(result.clone(), Some(guess.to_string()))) {
+            Ok(BotResponse::Ok) | Ok(BotResponse::Won) => { },
+            Ok(response) => println!("External strategy did not accept result: {}", response),
+            Err(err) => println!("Failed to update external strategy: {}", err),
+        }
+    }
+
+    fn print_hint(&self) {
+    }
+
+    // We can't ask the external strategy what it knows, so anything goes
+    fn answer_ok(&self, _eq: &Equation) -> Result<(), NerdleError> {
+        Ok(())
+    }
+
+    fn explain(&self, _eq: &Equation) -> Vec<ImpossibleReason> {
+        Vec::new()
+    }
+
+    // Changing the rules starts a new game, so this should be called before the first guess
+    fn set_commutative(&mut self, commutative: bool) {
+        let mode = if commutative { GameMode::Commutative } else { GameMode::Classic };
+        if let Err(err) = self.new_game(mode) {
+            println!("Failed to set external strategy mode: {}", err);
+        }
+    }
+
+    // The bot protocol has no way to change the rules, so this only warns if the program won't know about them
+    fn set_rules(&mut self, rules: RuleSet) {
+        if rules != RuleSet::default() {
+            println!("External strategy plays by the classic rules, not {}", rules);
+        }
+    }
+
+    fn state(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Drop for ExternalStrategy {
+    fn drop(&mut self) {
+        let process = self.process.get_mut();
+        let _ = process.send(&BotCommand::Quit);
+        let _ = process.child.wait();
+    }
+}
+
+impl fmt::Display for ExternalStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExternalStrategy({})", self.command)
+    }
+}
+
+#[cfg(all(test, unix))]
+fn fake_bot_script() -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::temp_dir().join(format!("nerdlebot-fake-bot-{}.sh", std::process::id()));
+    std::fs::write(&path, "#!/bin/sh\n\
+        while read cmd rest; do\n\
+          case \"$cmd\" in\n\
+            GUESS?) echo \"GUESS 12+34=46\" ;;\n\
+            QUIT) echo \"OK\"; exit ;;\n\
+            *) echo \"OK\" ;;\n\
+          esac\n\
+        done\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[cfg(unix)]
+#[test]
+fn external_strategy_test() {
+    let script = fake_bot_script();
+    let mut strategy = ExternalStrategy::new(&script).unwrap();
+    strategy.set_commutative(true);
+    let guess = strategy.take_guess().unwrap();
+    assert_eq!(guess.to_string(), "12+34=46");
+    strategy.update(&guess, &NerdleResult::from_str("--------").unwrap());
+    assert_eq!(strategy.to_string(), format!("ExternalStrategy({})", script));
+    drop(strategy);
+    std::fs::remove_file(&script).unwrap();
+}
+
+#[test]
+fn external_strategy_missing_test() {
+    assert!(ExternalStrategy::new("./no-such-strategy").is_err());
+    assert!(ExternalStrategy::new("").is_err());
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::eq::Equation;
+use crate::nerdle::{nerdle_str, NerdleResult, NerdleError, NERDLE_CHARACTERS};
+use crate::nerdledata::NerdleData;
+
+// Hints for human players, from a gentle nudge up to giving the answer away.  Each one says why it's true,
+// either from what earlier results showed or from what every remaining answer has in common.
+// Positions are numbered from 1 here, since these are for people.
+pub const HINT_LEVELS: u32 = 5;
+
+const OPERATORS: &[u8] = b"+-*/";
+
+pub struct Hinter {
+    candidates: Vec<String>,
+    data: NerdleData,
+}
+
+impl Hinter {
+    pub fn new(answers: Vec<String>) -> Hinter {
+        Hinter {
+            candidates: answers,
+            data: NerdleData::default(),
+        }
+    }
+
+    pub fn set_commutative(&mut self, commutative: bool) {
+        self.data.commutative = commutative;
+    }
+
+    #[cfg(test)]
+    pub fn candidates(&self) -> &Vec<String> {
+        &self.candidates
+    }
+
+    pub fn update(&mut self, guess: &Equation, res: &NerdleResult) {
+        self.data.update(guess, res);
+        let guess = guess.to_string();
+        let commutative = self.data.commutative && !res.won();
+        let wrong_canonical = &self.data.wrong_canonical;
+        self.candidates.retain(|candidate| {
+            nerdle_str(&guess, candidate).map(|actual| &actual == res).unwrap_or(false)
+                && !(commutative && Equation::from_str(candidate).map(|eq| wrong_canonical.contains(&eq.canonical())).unwrap_or(false))
+        });
+    }
+
+    pub fn hint(&self, level: u32) -> Result<String, NerdleError> {
+        if self.candidates.is_empty() {
+            return Err(NerdleError { message: "No answers fit the results so far".to_string()});
+        }
+        match level {
+            1 => Ok(self.remaining_hint()),
+            2 => Ok(self.operator_hint()),
+            3 => Ok(self.equals_hint()),
+            4 => Ok(self.digit_hint()),
+            5 => Ok(self.answer_hint()),
+            _ => Err(NerdleError { message: format!("Hint level must be 1 to {}, not {}", HINT_LEVELS, level)}),
+        }
+    }
+
+    fn remaining_hint(&self) -> String {
+        match self.candidates.len() {
+            1 => "Only 1 answer fits the results so far".to_string(),
+            n => format!("{} answers fit the results so far", n),
+        }
+    }
+
+    fn operator_hint(&self) -> String {
+        let known: Vec<char> = OPERATORS.iter()
+            .filter(|op| self.data.char_info.get(op).map(|info| info.min_count > 0).unwrap_or(false))
+            .map(|op| *op as char)
+            .collect();
+        let always: Vec<char> = OPERATORS.iter()
+            .filter(|op| self.candidates.iter().all(|candidate| candidate.as_bytes().contains(op)))
+            .map(|op| *op as char)
+            .collect();
+        let maybe: Vec<char> = OPERATORS.iter()
+            .filter(|op| !always.contains(&(**op as char)))
+            .filter(|op| self.candidates.iter().any(|candidate| candidate.as_bytes().contains(op)))
+            .map(|op| *op as char)
+            .collect();
+
+        let mut hint = String::new();
+        if !known.is_empty() {
+            hint.push_str(&format!("Earlier results showed {} in the answer. ", join_chars(&known)));
+        }
+        if !always.is_empty() {
+            hint.push_str(&format!("Every remaining answer uses {}", join_chars(&always)));
+            if !maybe.is_empty() {
+                hint.push_str(&format!(", and some also use {}", join_chars(&maybe)));
+            }
+        } else {
+            hint.push_str(&format!("Remaining answers use {}", join_chars(&maybe)));
+        }
+        hint
+    }
+
+    fn equals_hint(&self) -> String {
+        if let Some(pos) = self.data.equal_pos {
+            return format!("The equals sign is at position {}, shown green earlier", pos + 1);
+        }
+        let positions: BTreeSet<usize> = self.candidates.iter().filter_map(|candidate| candidate.find('=')).collect();
+        if positions.len() == 1 {
+            let pos = positions.iter().next().unwrap();
+            format!("The equals sign must be at position {}: it is there in every remaining answer", pos + 1)
+        } else {
+            let positions: Vec<String> = positions.iter().map(|pos| (pos + 1).to_string()).collect();
+            format!("The equals sign could be at position {}", positions.join(" or "))
+        }
+    }
+
+    // A digit in a position which earlier results haven't already shown, but every remaining answer agrees on
+    fn digit_hint(&self) -> String {
+        for pos in 0..NERDLE_CHARACTERS as usize {
+            if self.data.positions[pos].values().any(|is| *is) {
+                continue;
+            }
+            let chars: BTreeSet<u8> = self.candidates.iter().map(|candidate| candidate.as_bytes()[pos]).collect();
+            if chars.len() == 1 {
+                let ch = *chars.iter().next().unwrap();
+                if ch.is_ascii_digit() {
+                    return format!("Position {} must be {}: every remaining answer has it there", pos + 1, ch as char);
+                }
+            }
+        }
+        let digits: Vec<char> = (b'0'..=b'9')
+            .filter(|digit| self.candidates.iter().all(|candidate| candidate.as_bytes().contains(digit)))
+            .filter(|digit| self.data.char_info.get(digit).map(|info| info.min_count == 0).unwrap_or(true))
+            .map(|digit| digit as char)
+            .collect();
+        match digits.first() {
+            Some(digit) => format!("The answer contains {}, though its position isn't certain yet", digit),
+            None => "No digit is certain yet beyond what earlier results showed".to_string(),
+        }
+    }
+
+    fn answer_hint(&self) -> String {
+        match self.candidates.len() {
+            1 => format!("The answer is {}", self.candidates[0]),
+            n => format!("The answer could be {} (one of {} possibilities)", self.candidates[0], n),
+        }
+    }
+}
+
+fn join_chars(chars: &[char]) -> String {
+    chars.iter().map(|ch| ch.to_string()).collect::<Vec<String>>().join(" ")
+}
+
+// Parse a "hint N" command, with N defaulting to 1
+pub fn parse_hint_command(input: &str) -> Option<Result<u32, NerdleError>> {
+    let mut words = input.split_whitespace();
+    if words.next() != Some("hint") {
+        return None;
+    }
+    Some(match words.next() {
+        None => Ok(1),
+        Some(level) => level.parse::<u32>()
+            .map_err(|err| NerdleError { message: format!("Invalid hint level '{}': {}", level, err)}),
+    })
+}
+
+#[cfg(test)]
+fn hint_test_hinter() -> Hinter {
+    let mut hinter = Hinter::new(["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108", "4+3+3=10", "48-32=16", "20+30=50", "20+40=60"]
+        .iter().map(|eq| eq.to_string()).collect());
+    let guess = Equation::from_str("70+10=80").unwrap();
+    hinter.update(&guess, &NerdleResult::from_str("-GG-GG-G").unwrap());
+    hinter
+}
+
+#[test]
+fn hint_levels_test() {
+    let hinter = hint_test_hinter();
+    assert_eq!(hinter.candidates(), &vec!["20+30=50".to_string(), "20+40=60".to_string()]);
+    assert_eq!(hinter.hint(1).unwrap(), "2 answers fit the results so far");
+    assert!(hinter.hint(2).unwrap().starts_with("Earlier results showed + in the answer. Every remaining answer uses +"));
+    assert_eq!(hinter.hint(3).unwrap(), "The equals sign is at position 6, shown green earlier");
+    assert_eq!(hinter.hint(4).unwrap(), "Position 1 must be 2: every remaining answer has it there");
+    assert_eq!(hinter.hint(5).unwrap(), "The answer could be 20+30=50 (one of 2 possibilities)");
+    assert!(hinter.hint(6).is_err());
+}
+
+#[test]
+fn parse_hint_command_test() {
+    assert_eq!(parse_hint_command("hint").unwrap().unwrap(), 1);
+    assert_eq!(parse_hint_command("hint 4").unwrap().unwrap(), 4);
+    assert!(parse_hint_command("hint x").unwrap().is_err());
+    assert!(parse_hint_command("12+34=46").is_none());
+}
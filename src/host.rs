@@ -0,0 +1,209 @@
+use std::fmt;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::eq::Equation;
+use crate::nerdle::{nerdle, nerdle_commutative, NerdleResult, NerdleError};
+
+// Something that responds to guesses with results, like the real game does.
+pub trait NerdleHost {
+    fn respond(&mut self, guess: &Equation) -> Result<NerdleResult, NerdleError>;
+    // The answer, or one answer consistent with every response so far if the host hasn't committed to one
+    fn answer(&self) -> Option<Equation>;
+}
+
+// The normal game: the answer is chosen up front
+pub struct FixedAnswerHost {
+    answer: Equation,
+    commutative: bool,
+}
+
+impl FixedAnswerHost {
+    pub fn new(answer: Equation) -> FixedAnswerHost {
+        FixedAnswerHost {
+            answer,
+            commutative: false,
+        }
+    }
+
+    // Accept commuted forms of the answer as a win, like the real game does
+    pub fn set_commutative(&mut self, commutative: bool) {
+        self.commutative = commutative;
+    }
+}
+
+impl NerdleHost for FixedAnswerHost {
+    fn respond(&mut self, guess: &Equation) -> Result<NerdleResult, NerdleError> {
+        if self.commutative {
+            nerdle_commutative(guess, &self.answer)
+        } else {
+            nerdle(guess, &self.answer)
+        }
+    }
+
+    fn answer(&self) -> Option<Equation> {
+        Some(self.answer.clone())
+    }
+}
+
+// Absurdle-style host which never commits to an answer.  Each guess gets whichever result leaves the most
+// possible answers, so the player is always facing the worst case.
+pub struct AdversarialHost {
+    candidates: Vec<String>,
+    commutative: bool,
+}
+
+impl AdversarialHost {
+    pub fn new(candidates: Vec<String>) -> AdversarialHost {
+        AdversarialHost {
+            candidates,
+            commutative: false,
+        }
+    }
+
+    // Accept commuted forms of the answer as a win, like the real game does
+    pub fn set_commutative(&mut self, commutative: bool) {
+        self.commutative = commutative;
+    }
+
+    pub fn candidates(&self) -> &Vec<String> {
+        &self.candidates
+    }
+}
+
+impl NerdleHost for AdversarialHost {
+    fn respond(&mut self, guess: &Equation) -> Result<NerdleResult, NerdleError> {
+        // Check the guess before we start taking apart the candidates
+        if !guess.computes()? {
+            return Err(NerdleError { message: format!("Guess does not compute: {}", guess)});
+        }
+
+        // Score every candidate before replacing any, so a guess which fails part-way leaves them all in place
+        let mut buckets: HashMap<NerdleResult, Vec<String>> = HashMap::new();
+        for candidate in self.candidates.iter() {
+            let answer = Equation::from_str(candidate)
+                .map_err(|err| NerdleError { message: format!("Invalid candidate answer '{}': {}", candidate, err)})?;
+            let res = if self.commutative {
+                nerdle_commutative(guess, &answer)?
+            } else {
+                nerdle(guess, &answer)?
+            };
+            buckets.entry(res).or_default().push(candidate.clone());
+        }
+
+        // Largest bucket wins; on a tie avoid admitting the guess was right, then pick consistently
+        let (res, candidates) = buckets.into_iter()
+            .max_by(|(a_res, a), (b_res, b)| a.len().cmp(&b.len())
+                .then_with(|| b_res.won().cmp(&a_res.won()))
+                .then_with(|| b_res.to_string().cmp(&a_res.to_string())))
+            .ok_or_else(|| NerdleError { message: "No possible answers left".to_string() })?;
+        self.candidates = candidates;
+        Ok(res)
+    }
+
+    fn answer(&self) -> Option<Equation> {
+        self.candidates.first().and_then(|answer| Equation::from_str(answer).ok())
+    }
+}
+
+// Which kind of host to play against.  The values feed into the daily puzzle seed, so they must not change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameMode {
+    Classic = 0,
+    Commutative = 1,
+    Adversarial = 2,
+}
+
+impl GameMode {
+    // A host for this mode.  Adversarial hosts ignore the answer and choose among all of the answers instead.
+    pub fn host(&self, answer: Equation, answers: &[Equation]) -> Box<dyn NerdleHost> {
+        match self {
+            GameMode::Classic => Box::new(FixedAnswerHost::new(answer)),
+            GameMode::Commutative => {
+                let mut host = FixedAnswerHost::new(answer);
+                host.set_commutative(true);
+                Box::new(host)
+            },
+            GameMode::Adversarial => Box::new(AdversarialHost::new(answers.iter().map(|eq| eq.to_string()).collect())),
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "classic" => Ok(GameMode::Classic),
+            "commutative" => Ok(GameMode::Commutative),
+            "adversarial" => Ok(GameMode::Adversarial),
+            _ => Err(NerdleError { message: format!("Unrecognized game mode '{}'", input)})
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            GameMode::Classic => "classic",
+            GameMode::Commutative => "commutative",
+            GameMode::Adversarial => "adversarial",
+        })
+    }
+}
+
+#[cfg(test)]
+use crate::nerdle::nerdle_str;
+
+#[test]
+fn adversarial_host_test() {
+    let candidates: Vec<String> = ["12+34=46", "10+20=30", "20+30=50", "43-21=22"]
+        .iter().map(|eq| eq.to_string()).collect();
+    let mut host = AdversarialHost::new(candidates);
+
+    // Guessing one of the candidates should not win while others remain
+    let res = host.respond(&Equation::from_str("10+20=30").unwrap()).unwrap();
+    assert!(!res.won());
+    assert!(!host.candidates().contains(&"10+20=30".to_string()));
+    for candidate in host.candidates().iter() {
+        assert_eq!(nerdle_str("10+20=30", candidate).unwrap(), res);
+    }
+
+    // Eventually there is only one left and it has to admit it
+    let mut turns = 0;
+    loop {
+        turns += 1;
+        assert!(turns < 10);
+        let guess = host.answer().unwrap();
+        if host.respond(&guess).unwrap().won() {
+            break;
+        }
+    }
+    assert_eq!(host.candidates().len(), 1);
+}
+
+#[test]
+fn adversarial_host_bad_guess_test() {
+    let candidates: Vec<String> = ["12+34=46", "10+20=30", "20+30=50"].iter().map(|eq| eq.to_string()).collect();
+    let mut host = AdversarialHost::new(candidates);
+    assert!(host.respond(&Equation::from_str("1+2=3").unwrap()).is_err());
+    assert_eq!(host.candidates().len(), 3);
+    assert!(host.respond(&Equation::from_str("12+34=46").unwrap()).is_ok());
+    assert!(!host.candidates().is_empty());
+}
+
+#[test]
+fn commutative_host_test() {
+    let mut host = FixedAnswerHost::new(Equation::from_str("30+20=50").unwrap());
+    assert!(!host.respond(&Equation::from_str("20+30=50").unwrap()).unwrap().won());
+    host.set_commutative(true);
+    assert!(host.respond(&Equation::from_str("20+30=50").unwrap()).unwrap().won());
+}
+
+#[test]
+fn fixed_answer_host_test() {
+    let mut host = FixedAnswerHost::new(Equation::from_str("12+34=46").unwrap());
+    assert_eq!(host.respond(&Equation::from_str("10+20=30").unwrap()).unwrap().to_string(), "G-GY-GY-");
+    assert!(host.respond(&Equation::from_str("12+34=46").unwrap()).unwrap().won());
+    assert_eq!(host.answer().unwrap().to_string(), "12+34=46");
+}
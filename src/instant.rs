@@ -0,0 +1,86 @@
+use rand::seq::SliceRandom;
+
+use crate::eq::Equation;
+use crate::constraint::NoMatchFound;
+use crate::nerdle::{nerdle, NerdleResult};
+use crate::nerdledata::NerdleData;
+
+const ATTEMPTS: u32 = 1000;
+
+// Instant Nerdle: some guesses and their results are given up front, and the answer must be found in one shot
+pub struct InstantPuzzle {
+    pub clues: Vec<(Equation, NerdleResult)>,
+}
+
+impl InstantPuzzle {
+    pub fn new(clues: Vec<(Equation, NerdleResult)>) -> InstantPuzzle {
+        InstantPuzzle {
+            clues,
+        }
+    }
+
+    pub fn data(&self) -> NerdleData {
+        let mut data = NerdleData::default();
+        for (guess, res) in self.clues.iter() {
+            data.update(guess, res);
+        }
+        data
+    }
+
+    // Every answer consistent with all of the clues.  NerdleData rules out most answers quickly, then we check
+    // the rest exactly, since it doesn't capture everything a result tells us.
+    pub fn solutions(&self, answers: &[Equation]) -> Vec<Equation> {
+        let data = self.data();
+        answers.iter()
+            .filter(|answer| data.eq_matches(answer).is_ok())
+            .filter(|answer| self.clues.iter().all(|(guess, res)| nerdle(guess, answer).map(|actual| &actual == res).unwrap_or(false)))
+            .cloned()
+            .collect()
+    }
+}
+
+// Make a puzzle with `guesses` clues whose only solution is one of `answers`, returning the puzzle and the answer
+pub fn gen_instant(answers: &[Equation], guesses: usize) -> Result<(InstantPuzzle, Equation), NoMatchFound> {
+    let mut rng = rand::thread_rng();
+    for _try in 0..ATTEMPTS {
+        let answer = answers.choose(&mut rng)
+            .ok_or_else(|| NoMatchFound { message: "No answers to choose from".to_string() })?;
+        let clues: Vec<(Equation, NerdleResult)> = answers.choose_multiple(&mut rng, guesses)
+            .filter_map(|guess| nerdle(guess, answer).ok().map(|res| (guess.clone(), res)))
+            .collect();
+        // A clue that gives the answer away isn't much of a puzzle
+        if clues.len() != guesses || clues.iter().any(|(_, res)| res.won()) {
+            continue;
+        }
+
+        let puzzle = InstantPuzzle::new(clues);
+        if puzzle.solutions(answers).len() == 1 {
+            return Ok((puzzle, answer.clone()));
+        }
+    }
+    Err(NoMatchFound { message: format!("Could not generate a puzzle with a unique solution after {} attempts", ATTEMPTS) })
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn instant_solutions_test() {
+    let answers: Vec<Equation> = ["12+34=46", "10+20=30", "20+30=50"].iter().map(|eq| Equation::from_str(eq).unwrap()).collect();
+    let answer = Equation::from_str("20+30=50").unwrap();
+    let guess = Equation::from_str("10+20=30").unwrap();
+    let puzzle = InstantPuzzle::new(vec![(guess.clone(), nerdle(&guess, &answer).unwrap())]);
+    let solutions: Vec<String> = puzzle.solutions(&answers).iter().map(|eq| eq.to_string()).collect();
+    assert_eq!(solutions, vec!["20+30=50".to_string()]);
+}
+
+#[test]
+fn gen_instant_test() {
+    let answers: Vec<Equation> = ["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108", "20+30=50"]
+        .iter().map(|eq| Equation::from_str(eq).unwrap()).collect();
+    let (puzzle, answer) = gen_instant(&answers, 2).unwrap();
+    assert_eq!(puzzle.clues.len(), 2);
+    let solutions = puzzle.solutions(&answers);
+    assert_eq!(solutions.len(), 1);
+    assert_eq!(solutions[0].to_string(), answer.to_string());
+}
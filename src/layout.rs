@@ -0,0 +1,176 @@
+use std::fmt;
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use crate::constraint::{EquationConstraint, MIN_EQUATION_LENGTH};
+
+// Where the numbers go in an equation like "digits op digits [op digits] = digits": how many digits each operand
+// has, in order, then how many the result has and whether it has a minus sign.  The operators and equal sign fill
+// the gaps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    pub operands: Vec<usize>,
+    pub result: usize,
+    pub negative_result: bool,
+}
+
+impl Layout {
+    pub fn len(&self) -> usize {
+        self.operands.iter().sum::<usize>() + self.operands.len() + self.negative_result as usize + self.result
+    }
+
+    pub fn num_ops(&self) -> usize {
+        self.operands.len() - 1
+    }
+
+    // Position of the first digit of each operand
+    pub fn operand_starts(&self) -> Vec<usize> {
+        let mut starts = Vec::new();
+        let mut pos = 0;
+        for digits in self.operands.iter() {
+            starts.push(pos);
+            pos += digits + 1;
+        }
+        starts
+    }
+
+    pub fn equal_pos(&self) -> usize {
+        self.result_start() - self.negative_result as usize - 1
+    }
+
+    // Position of the first digit of the result, after any minus sign
+    pub fn result_start(&self) -> usize {
+        self.len() - self.result
+    }
+
+    // Whether every character this layout puts somewhere is allowed there
+    fn fits(&self, positions: &[HashSet<u8>]) -> bool {
+        let has = |pos: usize, chars: &[u8]| chars.iter().any(|ch| positions[pos].contains(ch));
+        let numbers_fit = |start: usize, digits: usize| {
+            (start..start + digits).all(|pos| has(pos, b"0123456789"))
+                // A number with more than one digit can't start with 0
+                && (digits == 1 || has(start, b"123456789"))
+        };
+        let starts = self.operand_starts();
+        starts.iter().zip(self.operands.iter()).all(|(start, digits)| numbers_fit(*start, *digits))
+            && starts.iter().skip(1).all(|start| has(start - 1, b"+-*/"))
+            && has(self.equal_pos(), b"=")
+            && (!self.negative_result || has(self.equal_pos() + 1, b"-"))
+            && numbers_fit(self.result_start(), self.result)
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, digits) in self.operands.iter().enumerate() {
+            if i > 0 {
+                write!(f, "o")?;
+            }
+            write!(f, "{}", "n".repeat(*digits))?;
+        }
+        write!(f, "={}{}", if self.negative_result { "-" } else { "" }, "n".repeat(self.result))
+    }
+}
+
+// Every layout of the constraint's length and number of operators whose digits, operators and equal sign can all
+// go where its positions allow, and whose result isn't too long for its range.  Only the positions are checked, so
+// each layout may still have no equations which work out.
+pub fn layouts(constraint: &EquationConstraint) -> Vec<Layout> {
+    let mut found = Vec::new();
+    if constraint.len() < MIN_EQUATION_LENGTH {
+        return found;
+    }
+    let limits = LayoutLimits {
+        positions: &constraint.positions,
+        num_ops: &constraint.num_ops,
+        max_operand: constraint.len() - MIN_EQUATION_LENGTH + 1,
+        max_result: constraint.c_constraint.range.end().to_string().len(),
+        negative_results: constraint.rules.negative_results,
+    };
+    let mut operands = Vec::new();
+    add_layouts(&limits, &mut operands, &mut found);
+    found
+}
+
+struct LayoutLimits<'a> {
+    positions: &'a [HashSet<u8>],
+    num_ops: &'a RangeInclusive<u32>,
+    // Most digits in an operand, and in the result
+    max_operand: usize,
+    max_result: usize,
+    negative_results: bool,
+}
+
+fn add_layouts(limits: &LayoutLimits, operands: &mut Vec<usize>, found: &mut Vec<Layout>) {
+    let positions = limits.positions;
+    let num_ops = limits.num_ops;
+    let used: usize = operands.iter().sum::<usize>() + operands.len();
+    if used >= positions.len() {
+        return;
+    }
+    if !operands.is_empty() && num_ops.contains(&(operands.len() as u32 - 1)) {
+        // The rest after the equal sign is the result
+        let rest = positions.len() - used;
+        for negative_result in [false, true].iter().cloned() {
+            if negative_result && (!limits.negative_results || rest < 2) {
+                continue;
+            }
+            let result = rest - negative_result as usize;
+            if result <= limits.max_result {
+                let layout = Layout { operands: operands.clone(), result, negative_result };
+                if layout.fits(positions) {
+                    found.push(layout);
+                }
+            }
+        }
+    }
+    if operands.len() as u32 > *num_ops.end() {
+        return;
+    }
+    for digits in 1..=limits.max_operand {
+        operands.push(digits);
+        add_layouts(limits, operands, found);
+        operands.pop();
+    }
+}
+
+#[cfg(test)]
+use crate::nerdle::NERDLE_CHARACTERS;
+
+#[test]
+fn layouts_test() {
+    let all = layouts(&EquationConstraint::default());
+    let shown: Vec<String> = all.iter().map(|layout| layout.to_string()).collect();
+    assert!(shown.contains(&"nnonn=nn".to_string()));
+    assert!(shown.contains(&"nonon=nn".to_string()));
+    assert!(shown.contains(&"nnnon=nn".to_string()));
+    assert!(!shown.contains(&"nnnnnn=n".to_string()));
+    assert!(all.iter().all(|layout| layout.len() == NERDLE_CHARACTERS as usize));
+
+    // With the equal sign known to be at position 5 and an operator at 2, only two-digit first operands fit
+    let mut known = EquationConstraint::default();
+    known.positions[2] = b"+-".iter().cloned().collect();
+    known.positions[5] = b"=".iter().cloned().collect();
+    for pos in [0, 1, 3, 4, 6, 7].iter() {
+        known.positions[*pos].retain(|ch| ch.is_ascii_digit());
+    }
+    let shown: Vec<String> = layouts(&known).iter().map(|layout| layout.to_string()).collect();
+    assert_eq!(shown, vec!["nnonn=nn"]);
+
+    // Other lengths work the same way, with room for more operators
+    let long = EquationConstraint::for_length(10);
+    let long_layouts = layouts(&long);
+    assert!(long_layouts.iter().any(|layout| layout.to_string() == "nonnonon=n"));
+    assert!(long_layouts.iter().all(|layout| layout.len() == 10 && layout.fits(&long.positions)));
+
+    // Negative results take a character for the sign, if the rules allow them at all
+    assert!(!shown_layouts(&EquationConstraint::default()).iter().any(|layout| layout.contains("=-")));
+    let mut negative = EquationConstraint::default();
+    negative.rules.negative_results = true;
+    assert!(shown_layouts(&negative).contains(&"nnonn=-n".to_string()));
+}
+
+#[cfg(test)]
+fn shown_layouts(constraint: &EquationConstraint) -> Vec<String> {
+    layouts(constraint).iter().map(|layout| layout.to_string()).collect()
+}
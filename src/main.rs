@@ -1,8 +1,7 @@
 use std::str::FromStr;
 use std::io;
+use std::io::Read;
 use std::fmt;
-use std::fs::File;
-use std::io::{BufReader, BufRead};
 use std::time::{Instant};
 use std::panic;
 
@@ -17,12 +16,41 @@ mod strategy;
 mod nerdsolver;
 mod nerdledata;
 mod util;
+mod share;
+mod reverse;
+mod host;
+mod multi;
+mod instant;
+mod server;
+mod bot;
+mod external;
+mod daily;
+mod answers;
+mod rating;
+mod evaluate;
+mod hint;
+mod explain;
+mod layout;
+mod rules;
 
 use crate::eq::Equation;
 use crate::expr::Expression;
-use crate::eqgen::eqgen;
+use crate::eqgen::{eqgen_all, eqgen_all_constrained, eqgen_constrained};
+use crate::constraint::EquationConstraint;
 use crate::strategy::{Strategy, StrategyEnum};
-use crate::nerdle::{NerdleResult, NERDLE_CHARACTERS};
+use crate::rules::RuleSet;
+use crate::nerdsolver::NerdleSolver;
+use crate::nerdle::{NerdleResult, NerdleError, NERDLE_CHARACTERS};
+use crate::share::NerdleShare;
+use crate::host::{NerdleHost, FixedAnswerHost, AdversarialHost};
+use crate::multi::{MultiNerdleGame, MultiNerdleSolver};
+use crate::instant::{InstantPuzzle, gen_instant};
+use crate::server::NerdleServer;
+use crate::bot::Bot;
+use crate::host::GameMode;
+use crate::answers::{AnswerList, AnswerFilter};
+use crate::hint::{Hinter, parse_hint_command};
+use crate::daily::{NerdleDate, DailyRecord, DailyArchive, daily_answer, daily_answers};
 
 #[derive(Clone)]
 pub struct CommandLineError {
@@ -80,11 +108,154 @@ macro_rules! skip_fail {
     };
 }
 
+// Strategies search exhaustively, so if one can't come up with a guess no answer fits the results and
+// trying again won't help
+macro_rules! give_up_without_guess {
+    ($solver:expr, $give_up:expr) => {
+        match $solver.take_guess() {
+            Ok(val) => val,
+            Err(e) => {
+                println!("No answers fit the results so far, giving up (Error {})", e);
+                if let Some(state) = $solver.state() {
+                    println!("Solver state: {}", state);
+                }
+                $give_up
+            }
+        }
+    };
+}
+
+// Let a human play against a host
+fn play_with_host(host: &mut dyn NerdleHost, puzzle: Option<u32>, commutative: bool, rules: RuleSet) -> NerdleShare {
+    let mut won = false;
+    let mut transcript = Vec::new();
+    let mut hinter = Hinter::new(answers_for_rules(rules).iter().map(|eq| eq.to_string()).collect());
+    hinter.set_commutative(commutative);
+
+    for turn in 1..=nerdle::NERDLE_TURNS {
+        let mut guess;
+        let res;
+        loop {
+            println!("Turn {} Enter Guess (or hint 1-{}):", turn, hint::HINT_LEVELS);
+            let mut input = String::new();
+            skip_fail!(io::stdin().read_line(&mut input), "Read error, try again");
+            let cleanput = input.trim_end();
+            println!("Read: {}", cleanput);
+            if let Some(level) = parse_hint_command(cleanput) {
+                print_hint(&hinter, level);
+                continue;
+            }
+            guess = skip_fail!(Equation::from_str(cleanput), "Invalid equation, try again");
+            res = skip_fail!(host.respond(&guess), "Nerdling failed try again");
+            break;
+        }
+        hinter.update(&guess, &res);
+
+        println!("Turn {} Result: {}", turn, res);
+        pretty_print_result(&guess.to_string(), &res);
+        transcript.push(res.clone());
+        if res.won() {
+            won = true;
+            println!("You won in {} turns!", turn);
+            break;
+        }
+    }
+    if let Some(answer) = host.answer() {
+        println!("Answer: {}", &answer);
+    }
+    if !won {
+        println!("You lost");
+    }
+    let share = NerdleShare::new(puzzle, transcript);
+    println!("{}", share);
+    share
+}
+
+// Every equation the rules allow, for hosts and hints to choose from
+fn answers_for_rules(rules: RuleSet) -> Vec<Equation> {
+    eqgen_all_constrained(&EquationConstraint { rules, ..Default::default() })
+}
+
+// Answers from a list file, keeping only those matching --tags if given
+fn load_answers(file_name: &str) -> Result<AnswerList, CommandLineError> {
+    let list = AnswerList::load(file_name)
+        .map_err(|err| CommandLineError { message: format!("{}", err) })?;
+    filter_answers(list)
+}
+
+fn filter_answers(list: AnswerList) -> Result<AnswerList, CommandLineError> {
+    match flag_value("--tags") {
+        Some(tags) => {
+            let filter = AnswerFilter::from_str(&tags)
+                .map_err(|err| CommandLineError { message: format!("Invalid --tags: {}", err) })?;
+            Ok(list.filter(&filter))
+        },
+        None => Ok(list),
+    }
+}
+
+// Everything that rules out a guess, falling back to the first reason found if the solver can't say more
+fn print_impossible(solver: &dyn Strategy, guess: &Equation, why: &NerdleError) {
+    let reasons = solver.explain(guess);
+    if reasons.is_empty() {
+        println!("Equation is impossible because {}", why);
+        return;
+    }
+    println!("Equation is impossible because:");
+    for reason in reasons.iter() {
+        println!("    {}", reason);
+    }
+}
+
+// Hints for "hint N" commands while playing
+fn print_hint(hinter: &Hinter, level: Result<u32, NerdleError>) {
+    match level.and_then(|level| hinter.hint(level)) {
+        Ok(hint) => println!("Hint: {}", hint),
+        Err(err) => println!("No hint: {}", err.message),
+    }
+}
+
+// Flags which can appear anywhere on the command line.  Everything else is a positional argument.
+const FLAGS: &[&str] = &["--commutative"];
+// Flags which take a value, given as --flag=value
+const VALUE_FLAGS: &[&str] = &["--strategy", "--mode", "--answers", "--archive", "--tags", "--where", "--rules"];
+
+fn is_flag(arg: &str) -> bool {
+    FLAGS.contains(&arg) || VALUE_FLAGS.iter().any(|flag| arg.starts_with(&format!("{}=", flag)))
+}
+
+fn args() -> Vec<String> {
+    std::env::args().filter(|arg| !is_flag(arg)).collect()
+}
+
+fn arg(n: usize) -> Option<String> {
+    args().into_iter().nth(n)
+}
+
+fn has_flag(flag: &str) -> bool {
+    std::env::args().any(|arg| arg == flag)
+}
+
+fn flag_value(flag: &str) -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix(&format!("{}=", flag)).map(|value| value.to_string()))
+}
+
 fn main() -> Result<(), CommandLineError> {
-    let cmd = std::env::args().nth(1);
+    let cmd = arg(1);
+    // Accept commuted forms of the answer as a win, like the real game
+    let commutative = has_flag("--commutative");
+    // Which strategy the solver commands play with, e.g. --strategy=external:./my_bot.py
+    let strategy_name = flag_value("--strategy").unwrap_or_else(|| "first_possible".to_string());
+    // Which equations count, e.g. --rules=zero_operands,negative_results
+    let rules = match flag_value("--rules") {
+        Some(rules) => RuleSet::from_str(&rules)
+            .map_err(|err| CommandLineError { message: format!("Invalid --rules: {}", err) })?,
+        None => RuleSet::default(),
+    };
+    let answer_constraint = EquationConstraint { rules, ..Default::default() };
     match cmd.as_deref() {
         Some("expr") => {
-            let expr = std::env::args().nth(2)
+            let expr = arg(2)
                 .expect("no expr given");
             let expr = Expression::from_str(&expr)
                 .expect("Failed to parse expression");
@@ -97,7 +268,7 @@ fn main() -> Result<(), CommandLineError> {
         },
 
         Some("eq") => {
-            let eq = std::env::args().nth(2)
+            let eq = arg(2)
                 .expect("no expr given");
             let eq = Equation::from_str(&eq)
                 .expect("Failed to parse equation");
@@ -110,8 +281,21 @@ fn main() -> Result<(), CommandLineError> {
         },
 
         Some("gen") => {
-            let eq = eqgen()
-                .expect("Failed to generate equation");
+            // e.g. --where="??+??=?? ; 7 notin ; pos1 in {1,2}"
+            let eq = match flag_value("--where") {
+                Some(pattern) => {
+                    let mut constraint = EquationConstraint::from_str(&pattern)
+                        .map_err(|err| CommandLineError { message: format!("Invalid --where: {}", err) })?;
+                    if flag_value("--rules").is_some() {
+                        constraint.rules = rules;
+                    }
+                    println!("Where: {}", constraint.pattern());
+                    println!("  Matches: {}", constraint.count());
+                    eqgen_constrained(&constraint)
+                        .map_err(|err| CommandLineError { message: format!("No equation matches: {}", err) })?
+                },
+                None => eqgen_constrained(&answer_constraint).expect("Failed to generate equation"),
+            };
             println!("Equation: {}", &eq);
             println!("  Length: {}", prettylen(eq.len()));
             let res = eq.computes()
@@ -132,13 +316,13 @@ fn main() -> Result<(), CommandLineError> {
         },
 
         Some("eval") => {
-            let answer = std::env::args().nth(2)
+            let answer = arg(2)
                 .expect("no expr given in arg 2");
             let answer = Equation::from_str(&answer)
                 .expect("Failed to parse equation in arg 2");
             println!("Answer: {}", &answer);
 
-            let guess = std::env::args().nth(3)
+            let guess = arg(3)
                 .expect("no expr given in arg 3");
             let guess = Equation::from_str(&guess)
                 .expect("Failed to parse equation in arg 3");
@@ -152,55 +336,46 @@ fn main() -> Result<(), CommandLineError> {
         },
 
         Some("play") => {
-            let answer = eqgen()
+            let answer = eqgen_constrained(&answer_constraint)
                 .expect("Failed to generate equation");
-            let mut won = false;
-
-            for turn in 1..=nerdle::NERDLE_TURNS {
-                let mut guess;
-                let res;
-                loop {
-                    println!("Turn {} Enter Guess:", turn);
-                    let mut input = String::new();
-                    skip_fail!(io::stdin().read_line(&mut input), "Read error, try again");
-                    let cleanput = input.trim_end();
-                    println!("Read: {}", cleanput);
-                    guess = skip_fail!(Equation::from_str(&cleanput), "Invalid equation, try again");
-                    res = skip_fail!(nerdle::nerdle(&guess, &answer), "Nerdling failed try again");
-                    break;
-                }
-
-                println!("Turn {} Result: {}", turn, res);
-                pretty_print_result(&guess.to_string(), &res);
-                if res.won() {
-                    won = true;
-                    println!("You won in {} turns!", turn);
-                    break;
-                }
-            }
-            println!("Answer: {}", &answer);
-            if !won {
-                println!("You lost");
-            }
+            let mut host = FixedAnswerHost::new(answer);
+            host.set_commutative(commutative);
+            play_with_host(&mut host, None, commutative, rules);
             Ok(())
         },
 
         // TODO: Lots of copypasta from "play"
         Some("play_assist") => {
-            let mut solver = StrategyEnum::by_name("first_possible")
+            let mut solver = StrategyEnum::by_name(&strategy_name)
                 .expect("Failed to find named strategy");
-            let answer = eqgen()
+            solver.set_commutative(commutative);
+            solver.set_rules(rules);
+            let answer = eqgen_constrained(&answer_constraint)
                 .expect("Failed to generate equation");
+            let mut host = FixedAnswerHost::new(answer.clone());
+            host.set_commutative(commutative);
             let mut won = false;
+            let mut transcript = Vec::new();
+            // Answers still consistent with every result so far, for scoring the player's guesses
+            let mut candidates: Vec<String> = evaluate::distinct_candidates(
+                &answers_for_rules(rules).iter().map(|eq| eq.to_string()).collect::<Vec<String>>(), commutative);
+            let mut skills = Vec::new();
 
             for turn in 1..=nerdle::NERDLE_TURNS {
                 let mut guess;
+                let mut bot_guess;
                 let res;
                 loop {
-                    match solver.take_guess() {
-                        Ok(bot_guess) => println!("Bot guess: {}", bot_guess),
-                        Err(err) => println!("Bot could not come up with guess: {}", err),
-                    }
+                    bot_guess = match solver.take_guess() {
+                        Ok(bot_guess) => {
+                            println!("Bot guess: {}", bot_guess);
+                            Some(bot_guess)
+                        },
+                        Err(err) => {
+                            println!("Bot could not come up with guess: {}", err);
+                            None
+                        },
+                    };
                     println!("Turn {} Enter Guess:", turn);
                     let mut input = String::new();
                     skip_fail!(io::stdin().read_line(&mut input), "Read error, try again");
@@ -209,14 +384,31 @@ fn main() -> Result<(), CommandLineError> {
                     guess = skip_fail!(Equation::from_str(&cleanput), "Invalid equation, try again");
                     match solver.answer_ok(&guess) {
                         Ok(()) => { },
-                        Err(why) => println!("Equation is impossible because {}", why)
+                        Err(why) => print_impossible(&solver, &guess, &why)
                     }
-                    res = skip_fail!(nerdle::nerdle(&guess, &answer), "Nerdling failed try again");
+                    res = skip_fail!(host.respond(&guess), "Nerdling failed try again");
                     break;
                 }
 
                 println!("Turn {} Result: {}", turn, res);
                 pretty_print_result(&guess.to_string(), &res);
+                transcript.push(res.clone());
+
+                match evaluate::evaluate_guess(&guess.to_string(), &candidates) {
+                    Ok(yours) => {
+                        println!("  Your guess {}", yours);
+                        let best = bot_guess.and_then(|bot_guess| evaluate::evaluate_guess(&bot_guess.to_string(), &candidates).ok());
+                        if let Some(best) = best {
+                            println!("  Bot guess  {}", best);
+                            let skill = evaluate::skill(&yours, &best);
+                            println!("  Turn {} skill: {:.0}%", turn, skill);
+                            skills.push(skill);
+                        }
+                    },
+                    Err(err) => println!("Could not evaluate guess: {}", err),
+                }
+                candidates = evaluate::filter_candidates(&candidates, &guess.to_string(), &res, commutative);
+
                 if res.won() {
                     won = true;
                     println!("You won in {} turns!", turn);
@@ -229,12 +421,16 @@ fn main() -> Result<(), CommandLineError> {
             if !won {
                 println!("You lost");
             }
+            if !skills.is_empty() {
+                println!("Game skill: {:.0}%", skills.iter().sum::<f64>() / skills.len() as f64);
+            }
+            println!("{}", NerdleShare::new(None, transcript));
             Ok(())
         },
 
         // TODO: Lots of duplicated code
         Some("solve_random") => {
-            let count = std::env::args().nth(2).map(|x| i32::from_str(&x).expect("Invalid number of games")).unwrap_or(1);
+            let count = arg(2).map(|x| i32::from_str(&x).expect("Invalid number of games")).unwrap_or(1);
             let mut wins = 0;
             let mut losses = 0;
             let mut win_turn_hist = [0; nerdle::NERDLE_TURNS as usize];
@@ -244,13 +440,17 @@ fn main() -> Result<(), CommandLineError> {
                 let start_time = Instant::now();
 
                 let result = panic::catch_unwind(|| {
-                    let mut solver = StrategyEnum::by_name("first_possible")
+                    let mut solver = StrategyEnum::by_name(&strategy_name)
                         .expect("Failed to find named strategy");
-                    let answer = eqgen().expect("Failed to generate equation");
+                    solver.set_commutative(commutative);
+                    solver.set_rules(rules);
+                    let answer = eqgen_constrained(&answer_constraint).expect("Failed to generate equation");
                     println!("Answer: {}", &answer);
+                    let mut host = FixedAnswerHost::new(answer);
+                    host.set_commutative(commutative);
 
                     let mut turn: u32 = 0;
-                    loop {
+                    'turns: loop {
                         turn += 1;
                         if turn > nerdle::NERDLE_TURNS {
                             break GameResult::Loss();
@@ -259,13 +459,13 @@ fn main() -> Result<(), CommandLineError> {
                         let mut guess;
                         let res;
                         loop {
-                            guess = skip_fail!(solver.take_guess(), "No valid guess was generating, trying again");
+                            guess = give_up_without_guess!(solver, break 'turns GameResult::Loss());
                             println!("Turn {}  Guess: {}", turn, guess);
                             match solver.answer_ok(&guess) {
                                 Ok(()) => { },
-                                Err(why) => println!("Equation is impossible because {}", why)
+                                Err(why) => print_impossible(&solver, &guess, &why)
                             }
-                            res = skip_fail!(nerdle::nerdle(&guess, &answer), "Nerdling failed, trying again");
+                            res = skip_fail!(host.respond(&guess), "Nerdling failed, trying again");
                             break;
                         }
 
@@ -307,7 +507,7 @@ fn main() -> Result<(), CommandLineError> {
         // TODO: Lots of duplicated code
         Some("solve") => {
             let result = panic::catch_unwind(|| {
-                let answer = std::env::args().nth(2)
+                let answer = arg(2)
                     .expect("no expr given");
                 let answer = Equation::from_str(&answer)
                     .expect("Failed to parse equation");
@@ -322,13 +522,18 @@ fn main() -> Result<(), CommandLineError> {
                     return Err(CommandLineError { message: format!("Equation unexpectedly did not compute: {}", answer) } );
                 }
         
-                let mut solver = StrategyEnum::by_name("first_possible")
+                let mut solver = StrategyEnum::by_name(&strategy_name)
                     .expect("Failed to find named strategy");
+                solver.set_commutative(commutative);
+                solver.set_rules(rules);
+                let mut host = FixedAnswerHost::new(answer.clone());
+                host.set_commutative(commutative);
 
                 println!("Answer: {}", &answer);
 
                 let mut won = false;
-                for turn in 1..=nerdle::NERDLE_TURNS {
+                let mut transcript = Vec::new();
+                'turns: for turn in 1..=nerdle::NERDLE_TURNS {
                     let mut guess;
                     let res;
                     loop {
@@ -336,24 +541,25 @@ fn main() -> Result<(), CommandLineError> {
                             Err(err) =>  return Err(CommandLineError { message: format!("Solver {} rejects answer: {}", solver, err) } ),
                             Ok(()) => { }
                         }
-                        guess = match std::env::args().nth(2 + turn as usize) {
+                        guess = match arg(2 + turn as usize) {
                             Some(guess) => match Equation::from_str(&guess) {
                                 Ok(guess) => guess,
                                 Err(err) => return Err(CommandLineError { message: format!("Invalid guess equation in command-line arg {} '{}': {}", 2+turn, guess, err) } )
                             }
-                            None => skip_fail!(solver.take_guess(), "No valid guess was generating, trying again")
+                            None => give_up_without_guess!(solver, break 'turns)
                         };
                         println!("Turn {}  Guess: {}", turn, guess);
                         match solver.answer_ok(&guess) {
                             Ok(()) => { },
-                            Err(why) => println!("Equation is impossible because {}", why)
+                            Err(why) => print_impossible(&solver, &guess, &why)
                         }
-                        res = skip_fail!(nerdle::nerdle(&guess, &answer), "Nerdling failed, trying again");
+                        res = skip_fail!(host.respond(&guess), "Nerdling failed, trying again");
                         break;
                     }
 
                     println!("Turn {} Result: {}", turn, res);
                     pretty_print_result(&guess.to_string(), &res);
+                    transcript.push(res.clone());
                     if res.won() {
                         won = true;
                         println!("I won in {} turns!", turn);
@@ -365,6 +571,7 @@ fn main() -> Result<(), CommandLineError> {
                 if !won {
                     println!("I lost");
                 }
+                println!("{}", NerdleShare::new(None, transcript));
                 Ok(())
             });
             match result {
@@ -376,11 +583,9 @@ fn main() -> Result<(), CommandLineError> {
 
         // TODO: Lots of duplicated code
         Some("solve_file") => {
-            let file_name = std::env::args().nth(2)
+            let file_name = arg(2)
                 .expect("Expected file name in arg 2");
-            let file = File::open(&file_name)
-                .expect(&format!("Error opening file '{}'", &file_name));
-            let buf_reader = BufReader::new(file);
+            let list = load_answers(&file_name)?;
 
             let mut wins = 0;
             let mut losses = 0;
@@ -388,23 +593,16 @@ fn main() -> Result<(), CommandLineError> {
 
             let mut i = 0;
 
-            for line in buf_reader.lines() {
-                let line = line.expect(&format!("Error readline line from file '{}'", &file_name));
-                let line = line.trim();
-
-                match line.chars().next() {
-                    None => continue, // Empty line (after removing whitespace)
-                    Some('#') => continue, // Comment line
-                    _ => { }
-                };
-
+            for answer in list.answers.iter() {
                 println!("=== Playing game {}", i);
                 let start_time = Instant::now();
 
-                let mut solver = StrategyEnum::by_name("first_possible")
+                let mut solver = StrategyEnum::by_name(&strategy_name)
                     .expect("Failed to find named strategy");
-                let answer = Equation::from_str(&line)
-                    .expect("Failed to parse equation");
+                solver.set_commutative(commutative);
+                solver.set_rules(rules);
+                let mut host = FixedAnswerHost::new(answer.clone());
+                host.set_commutative(commutative);
 
                 match answer.len() {
                     Ok(len) => if len != NERDLE_CHARACTERS as usize {
@@ -419,7 +617,7 @@ fn main() -> Result<(), CommandLineError> {
                 println!("Answer: {}", &answer);
 
                 let mut won = false;
-                for turn in 1..=nerdle::NERDLE_TURNS {
+                'turns: for turn in 1..=nerdle::NERDLE_TURNS {
                     let mut guess;
                     let res;
                     loop {
@@ -427,13 +625,13 @@ fn main() -> Result<(), CommandLineError> {
                             Err(err) =>  return Err(CommandLineError { message: format!("Solver {} rejects answer: {}", solver, err) } ),
                             Ok(()) => { }
                         }
-                        guess = skip_fail!(solver.take_guess(), "No valid guess was generating, trying again");
+                        guess = give_up_without_guess!(solver, break 'turns);
                         println!("Turn {}  Guess: {}", turn, guess);
                         match solver.answer_ok(&guess) {
                             Ok(()) => { },
-                            Err(why) => println!("Equation is impossible because {}", why)
+                            Err(why) => print_impossible(&solver, &guess, &why)
                         }
-                        res = skip_fail!(nerdle::nerdle(&guess, &answer), "Nerdling failed, trying again");
+                        res = skip_fail!(host.respond(&guess), "Nerdling failed, trying again");
                         break;
                     }
 
@@ -471,33 +669,49 @@ fn main() -> Result<(), CommandLineError> {
 
         // TODO: Lots of duplicated code
         Some("interactive") => {        
-            let mut solver = StrategyEnum::by_name("first_possible")
+            let mut solver = StrategyEnum::by_name(&strategy_name)
                 .expect("Failed to find named strategy");
+            solver.set_commutative(commutative);
+            solver.set_rules(rules);
 
 
             let mut won = false;
-            for turn in 1..=nerdle::NERDLE_TURNS {
+            let mut transcript = Vec::new();
+            let mut hinter = Hinter::new(answers_for_rules(rules).iter().map(|eq| eq.to_string()).collect());
+            hinter.set_commutative(commutative);
+            'turns: for turn in 1..=nerdle::NERDLE_TURNS {
                 // No idea why res should be mut but not guess?..
                 let guess;
                 let mut res;
                 loop {
-                    guess = match std::env::args().nth(1 + turn as usize) {
+                    guess = match arg(1 + turn as usize) {
                         Some(guess) => match Equation::from_str(&guess) {
                             Ok(guess) => guess,
                             Err(err) => return Err(CommandLineError { message: format!("Invalid guess equation in command-line arg {} '{}': {}", 2+turn, guess, err) } )
                         }
-                        None => skip_fail!(solver.take_guess(), "No valid guess was generating, trying again")
+                        None => give_up_without_guess!(solver, break 'turns)
                     };
                     println!("Turn {}  Guess: {}", turn, &guess);
                     match solver.answer_ok(&guess) {
                         Ok(()) => { },
-                        Err(why) => println!("Equation is impossible because {}", why)
+                        Err(why) => print_impossible(&solver, &guess, &why)
                     }
                     res = loop {
-                        println!("Turn {} Enter Result:", turn);
+                        println!("Turn {} Enter Result (or hint 1-{}, or dump):", turn, hint::HINT_LEVELS);
                         let mut input = String::new();
                         skip_fail!(io::stdin().read_line(&mut input), "Read error, try again");
                         let cleanput = input.trim();
+                        if let Some(level) = parse_hint_command(cleanput) {
+                            print_hint(&hinter, level);
+                            continue;
+                        }
+                        if cleanput == "dump" {
+                            match solver.state() {
+                                Some(state) => println!("{}", state),
+                                None => println!("Strategy {} has no state to dump", solver),
+                            }
+                            continue;
+                        }
                         res = skip_fail!(NerdleResult::from_str(cleanput), "Invalid entry");
                         break res;
                     };
@@ -506,6 +720,98 @@ fn main() -> Result<(), CommandLineError> {
 
                 println!("Turn {} Result: {}", turn, &res);
                 pretty_print_result(&guess.to_string(), &res);
+                transcript.push(res.clone());
+                hinter.update(&guess, &res);
+                if res.won() {
+                    won = true;
+                    println!("I won in {} turns!", turn);
+                    break;
+                }
+                solver.update(&guess, &res);
+                solver.print_hint();
+            }
+            if !won {
+                println!("I lost");
+            }
+            println!("{}", NerdleShare::new(None, transcript));
+
+            Ok(())
+        },
+
+        // Pick up a game dumped by the solver, to see what it makes of it
+        Some("load_state") => {
+            let file = arg(2).ok_or_else(|| CommandLineError { message: "Missing state file".to_string() })?;
+            let json = std::fs::read_to_string(&file)
+                .map_err(|err| CommandLineError { message: format!("Could not read state file '{}': {}", file, err) })?;
+            let solver = NerdleSolver::from_json(&json)
+                .map_err(|err| CommandLineError { message: format!("Could not load state file '{}': {}", file, err) })?;
+            println!("Constraint: {}", solver.constraint());
+            solver.print_hint();
+            match solver.take_guess() {
+                Ok(guess) => println!("Guess: {}", guess),
+                Err(err) => println!("No guess: {}", err),
+            }
+            Ok(())
+        },
+
+        Some("bot") => {
+            let strategy = arg(2).unwrap_or_else(|| strategy_name.clone());
+            let mut bot = Bot::new(&strategy)
+                .map_err(|err| CommandLineError { message: format!("Could not start bot: {}", err) })?;
+            bot.run(io::stdin().lock(), io::stdout().lock())
+                .map_err(|err| CommandLineError { message: format!("Bot I/O failed: {}", err) })
+        },
+
+        Some("share") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)
+                .expect("Failed to read share text from stdin");
+            let share = NerdleShare::from_str(&input)
+                .map_err(|err| CommandLineError { message: format!("Could not parse share text: {}", err) })?;
+            match share.puzzle {
+                Some(puzzle) => println!("Puzzle: {}", puzzle),
+                None => println!("Puzzle: unknown"),
+            }
+            for (turn, res) in share.rows.iter().enumerate() {
+                println!("Turn {} Result: {}", turn + 1, res);
+            }
+            match share.turns() {
+                Some(turns) => println!("Won in {} turns", turns),
+                None => println!("Lost"),
+            }
+            Ok(())
+        },
+        Some("play_adversarial") => {
+            let mut host = AdversarialHost::new(answers_for_rules(rules).iter().map(|eq| eq.to_string()).collect());
+            host.set_commutative(commutative);
+            play_with_host(&mut host, None, commutative, rules);
+            Ok(())
+        },
+
+        Some("solve_adversarial") => {
+            let mut solver = StrategyEnum::by_name(&strategy_name)
+                .expect("Failed to find named strategy");
+            solver.set_commutative(commutative);
+            solver.set_rules(rules);
+            let mut host = AdversarialHost::new(answers_for_rules(rules).iter().map(|eq| eq.to_string()).collect());
+            host.set_commutative(commutative);
+
+            let mut won = false;
+            let mut transcript = Vec::new();
+            'turns: for turn in 1..=nerdle::NERDLE_TURNS {
+                let mut guess;
+                let res;
+                loop {
+                    guess = give_up_without_guess!(solver, break 'turns);
+                    println!("Turn {}  Guess: {}", turn, guess);
+                    res = skip_fail!(host.respond(&guess), "Nerdling failed, trying again");
+                    break;
+                }
+
+                println!("Turn {} Result: {}", turn, res);
+                pretty_print_result(&guess.to_string(), &res);
+                println!("{} possible answers remain", host.candidates().len());
+                transcript.push(res.clone());
                 if res.won() {
                     won = true;
                     println!("I won in {} turns!", turn);
@@ -514,12 +820,281 @@ fn main() -> Result<(), CommandLineError> {
                 solver.update(&guess, &res);
                 solver.print_hint();
             }
+            if let Some(answer) = host.answer() {
+                println!("Answer: {}", &answer);
+            }
             if !won {
                 println!("I lost");
             }
+            println!("{}", NerdleShare::new(None, transcript));
+            Ok(())
+        },
+
+        Some("solve_multi") => {
+            let boards = arg(2).map(|x| usize::from_str(&x).expect("Invalid number of boards")).unwrap_or(2);
+            let answers = (0..boards).map(|_| eqgen_constrained(&answer_constraint).expect("Failed to generate equation")).collect();
+            let mut game = MultiNerdleGame::new(answers);
+            game.set_commutative(commutative);
+            let mut solver = MultiNerdleSolver::new(boards);
+            solver.set_commutative(commutative);
+            solver.set_rules(rules);
+            for (board, answer) in game.answers().iter().enumerate() {
+                println!("Board {} Answer: {}", board, answer);
+            }
+
+            'turns: for turn in 1..=game.max_turns() {
+                let mut guess;
+                let results;
+                loop {
+                    guess = give_up_without_guess!(solver, break 'turns);
+                    println!("Turn {}  Guess: {}", turn, guess);
+                    results = skip_fail!(game.guess(&guess), "Nerdling failed, trying again");
+                    break;
+                }
+
+                for (board, res) in results.iter().enumerate() {
+                    if let Some(res) = res {
+                        println!("Turn {} Board {} Result: {}", turn, board, res);
+                        pretty_print_result(&guess.to_string(), res);
+                    }
+                }
+                if game.won() {
+                    println!("I won all {} boards in {} turns!", boards, turn);
+                    break;
+                }
+                solver.update(&guess, &results);
+            }
+            if !game.won() {
+                println!("I lost, solved {} of {} boards", game.solved().iter().filter(|solved| **solved).count(), boards);
+            }
+            Ok(())
+        },
+
+        Some("instant") => {
+            let answers = answers_for_rules(rules);
+            let args: Vec<String> = args().into_iter().skip(2).collect();
+            if args.is_empty() {
+                // No clues given, so make up a puzzle and let the user solve it
+                let (puzzle, answer) = gen_instant(&answers, 2)
+                    .map_err(|err| CommandLineError { message: format!("Failed to generate puzzle: {}", err) })?;
+                for (turn, (guess, res)) in puzzle.clues.iter().enumerate() {
+                    println!("Turn {}  Guess: {}", turn + 1, guess);
+                    println!("Turn {} Result: {}", turn + 1, res);
+                    pretty_print_result(&guess.to_string(), res);
+                }
+                let guess = loop {
+                    println!("Enter Answer:");
+                    let mut input = String::new();
+                    skip_fail!(io::stdin().read_line(&mut input), "Read error, try again");
+                    break skip_fail!(Equation::from_str(input.trim_end()), "Invalid equation, try again");
+                };
+                let res = nerdle::nerdle(&guess, &answer)
+                    .map_err(|err| CommandLineError { message: format!("Nerdling failed: {}", err) })?;
+                pretty_print_result(&guess.to_string(), &res);
+                if res.won() {
+                    println!("You got it!");
+                } else {
+                    println!("You lost, answer: {}", answer);
+                }
+                return Ok(());
+            }
+
+            if !args.len().is_multiple_of(2) {
+                return Err(CommandLineError { message: "Expected pairs of guess and result".to_string() });
+            }
+            let mut clues = Vec::new();
+            for pair in args.chunks(2) {
+                let guess = Equation::from_str(&pair[0])
+                    .map_err(|err| CommandLineError { message: format!("Invalid guess equation '{}': {}", pair[0], err) })?;
+                let res = NerdleResult::from_str(&pair[1])
+                    .map_err(|err| CommandLineError { message: format!("Invalid result '{}': {}", pair[1], err) })?;
+                clues.push((guess, res));
+            }
+            let solutions = InstantPuzzle::new(clues).solutions(&answers);
+            for answer in solutions.iter() {
+                println!("Possible answer: {}", answer);
+            }
+            match solutions.len() {
+                0 => println!("No answer fits these clues"),
+                1 => println!("Answer is unique: {}", solutions[0]),
+                n => println!("{} answers fit these clues", n),
+            }
+            Ok(())
+        },
+
+        Some("rate") => {
+            let eq = arg(2)
+                .expect("no equation given");
+            let eq = Equation::from_str(&eq)
+                .expect("Failed to parse equation");
+            let samples = arg(3).map(|x| usize::from_str(&x).expect("Invalid number of samples")).unwrap_or(5);
+            let answers: Vec<String> = eqgen_all().iter().map(|eq| eq.to_string()).collect();
+            let rating = rating::rate(&eq, &answers, &strategy_name, samples)
+                .map_err(|err| CommandLineError { message: format!("Rating failed: {}", err) })?;
+            println!("{}", rating);
+            Ok(())
+        },
+
+        // Rate every answer in a file and list them hardest first
+        Some("rate_file") => {
+            let file_name = arg(2)
+                .expect("Expected file name in arg 2");
+            let samples = arg(3).map(|x| usize::from_str(&x).expect("Invalid number of samples")).unwrap_or(3);
+            let list = load_answers(&file_name)?;
+            let answers: Vec<String> = eqgen_all().iter().map(|eq| eq.to_string()).collect();
+            let mut ratings = Vec::new();
+            for (i, eq) in list.answers.iter().enumerate() {
+                println!("=== Rating {} / {}: {}", i + 1, list.answers.len(), eq);
+                ratings.push(rating::rate(eq, &answers, &strategy_name, samples)
+                    .map_err(|err| CommandLineError { message: format!("Rating {} failed: {}", eq, err) })?);
+            }
+            ratings.sort_by(|a, b| b.score().partial_cmp(&a.score()).unwrap_or(std::cmp::Ordering::Equal));
+
+            println!("{:<10} {:>6} {:>6} {:>9} {:>8} {:>5}  Layout", "Equation", "Score", "Turns", "Neighbors", "Repeats", "Trap");
+            for rating in ratings.iter() {
+                println!("{:<10} {:>6.2} {:>6} {:>9} {:>8} {:>5}  {}",
+                    rating.equation,
+                    rating.score(),
+                    rating.expected_turns.map(|turns| format!("{:.2}", turns)).unwrap_or_else(|| "-".to_string()),
+                    rating.neighbors.len(),
+                    rating.repeated_digits,
+                    if rating.known_trap { "yes" } else { "" },
+                    rating.layout.join(", "));
+            }
+            Ok(())
+        },
+
+        // answers FILE [tags|check|clean OUTFILE] [--tags=ops=2,difficulty=hard,pattern=NNONN=NN]
+        Some("answers") => {
+            let file_name = arg(2)
+                .expect("Expected file name in arg 2");
+            let mut list = load_answers(&file_name)?;
+            list.rules = rules;
+            match arg(3).as_deref() {
+                None | Some("tags") => {
+                    for (answer, tags) in list.tagged() {
+                        println!("{:<10} {}", answer, tags);
+                    }
+                    println!("{} answers", list.answers.len());
+                },
+                Some("check") => {
+                    for err in list.validate() {
+                        println!("Invalid: {}", err.message);
+                    }
+                    for removed in list.dedupe_commutative() {
+                        println!("Duplicate: {}", removed);
+                    }
+                },
+                Some("clean") => {
+                    let out_file_name = arg(4)
+                        .expect("Expected output file name in arg 4");
+                    let invalid = list.remove_invalid();
+                    let removed = list.dedupe_commutative();
+                    list.save(&out_file_name)
+                        .map_err(|err| CommandLineError { message: format!("{}", err) })?;
+                    println!("Saved {} answers to {}, dropping {} invalid and {} duplicates", list.answers.len(), out_file_name, invalid.len(), removed.len());
+                },
+                Some(other) => return Err(CommandLineError { message: format!("Unknown answers subcommand '{}'", other) }),
+            }
+            Ok(())
+        },
+
+        // daily [YYYY-MM-DD|archive] [--mode=classic|commutative|adversarial] [--answers=FILE] [--archive=FILE]
+        Some("daily") => {
+            let archive = DailyArchive::new(&flag_value("--archive").unwrap_or_else(|| "daily_archive.txt".to_string()));
+            if arg(2).as_deref() == Some("archive") {
+                let records = archive.records()
+                    .map_err(|err| CommandLineError { message: format!("Could not read archive: {}", err) })?;
+                let mut win_turn_hist = [0; nerdle::NERDLE_TURNS as usize];
+                for record in records.iter() {
+                    println!("{} {:<12} {}", record.date, record.mode, record.share.turns().map(|turns| turns.to_string()).unwrap_or_else(|| "X".to_string()));
+                    if let Some(turns) = record.share.turns() {
+                        win_turn_hist[turns - 1] += 1;
+                    }
+                }
+                let wins: i32 = win_turn_hist.iter().sum();
+                println!("{} played, {} won", records.len(), wins);
+                for (turn, wins) in win_turn_hist.iter().enumerate() {
+                    println!(" Turn {} wins {}", turn + 1, wins);
+                }
+                return Ok(());
+            }
+
+            let date = match arg(2) {
+                Some(date) => NerdleDate::from_str(&date)
+                    .map_err(|err| CommandLineError { message: format!("{}", err) })?,
+                None => NerdleDate::today(),
+            };
+            let mode = match flag_value("--mode") {
+                Some(mode) => GameMode::from_str(&mode)
+                    .map_err(|err| CommandLineError { message: format!("{}", err) })?,
+                None if commutative => GameMode::Commutative,
+                None => GameMode::Classic,
+            };
+            let answers = match flag_value("--answers") {
+                Some(file_name) => load_answers(&file_name)?,
+                None => filter_answers(daily_answers()
+                    .map_err(|err| CommandLineError { message: format!("Built-in daily answers are broken: {}", err) })?)?,
+            }.answers;
+            let answer = daily_answer(&date, mode, &answers)
+                .map_err(|err| CommandLineError { message: format!("No puzzle for {}: {}", date, err) })?;
+            if let Ok(Some(record)) = archive.find(&date, mode) {
+                println!("Already played {} ({}):", date, mode);
+                println!("{}", record.share);
+                return Ok(());
+            }
 
+            println!("Daily puzzle #{} for {} ({})", date.puzzle().unwrap_or(0), date, mode);
+            let mut host = mode.host(answer, &answers);
+            let share = play_with_host(host.as_mut(), date.puzzle(), mode == GameMode::Commutative, rules);
+            archive.add(&DailyRecord { date, mode, share })
+                .map_err(|err| CommandLineError { message: format!("Could not save result: {}", err) })
+        },
+
+        Some("serve") => {
+            let port = arg(2).map(|x| u16::from_str(&x).expect("Invalid port")).unwrap_or(8080);
+            NerdleServer::new(rules).serve(port)
+                .map_err(|err| CommandLineError { message: format!("Server failed: {}", err) })
+        },
+
+        Some("reverse") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)
+                .expect("Failed to read share text from stdin");
+            let share = NerdleShare::from_str(&input)
+                .map_err(|err| CommandLineError { message: format!("Could not parse share text: {}", err) })?;
+            let equations: Vec<String> = eqgen_all().iter().map(|eq| eq.to_string()).collect();
+
+            match arg(2) {
+                Some(answer) => {
+                    let answer = Equation::from_str(&answer)
+                        .expect("Failed to parse equation");
+                    let turns = reverse::possible_guesses(&share.rows, &answer.to_string(), &equations);
+                    for (turn, guesses) in turns.iter().enumerate() {
+                        println!("Turn {} Result: {} could be {} guesses", turn + 1, share.rows[turn], guesses.len());
+                        for guess in guesses.iter() {
+                            println!("    {}", guess);
+                        }
+                    }
+                    if turns.iter().any(|guesses| guesses.is_empty()) {
+                        println!("Impossible: no guesses could produce this grid for answer {}", answer);
+                    }
+                },
+                None => {
+                    let answers = reverse::possible_answers(&share.rows, &equations, &equations);
+                    for answer in answers.iter() {
+                        println!("Possible answer: {}", answer);
+                    }
+                    if answers.is_empty() {
+                        println!("Impossible: no answer could produce this grid");
+                    } else {
+                        println!("{} of {} answers are possible", answers.len(), equations.len());
+                    }
+                }
+            }
             Ok(())
         },
+
         Some(oops) => Err(CommandLineError { message: format!("Unrecognized command '{}'", oops) } ),
 
         None => Err(CommandLineError { message: format!("Missing command line flag") } ),
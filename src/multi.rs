@@ -0,0 +1,199 @@
+use crate::eq::Equation;
+use crate::strategy::Strategy;
+use crate::constraint::NoMatchFound;
+use crate::nerdle::{nerdle, nerdle_commutative, NerdleResult, NerdleError, NERDLE_TURNS};
+use crate::nerdsolver::NerdleSolver;
+use crate::rules::RuleSet;
+
+// Bi-Nerdle and Quad-Nerdle: each guess is scored against several hidden answers at once
+pub const BINERDLE_TURNS: u32 = 7;
+pub const QUADNERDLE_TURNS: u32 = 10;
+
+pub fn turns_for_boards(boards: usize) -> u32 {
+    match boards {
+        1 => NERDLE_TURNS,
+        2 => BINERDLE_TURNS,
+        4 => QUADNERDLE_TURNS,
+        // Not a real game mode, but give one extra turn per extra board
+        n => NERDLE_TURNS + n as u32 - 1,
+    }
+}
+
+pub struct MultiNerdleGame {
+    answers: Vec<Equation>,
+    solved: Vec<bool>,
+    commutative: bool,
+}
+
+impl MultiNerdleGame {
+    pub fn new(answers: Vec<Equation>) -> MultiNerdleGame {
+        let solved = vec![false; answers.len()];
+        MultiNerdleGame {
+            answers,
+            solved,
+            commutative: false,
+        }
+    }
+
+    // Accept commuted forms of each answer as a win, like the real game does
+    pub fn set_commutative(&mut self, commutative: bool) {
+        self.commutative = commutative;
+    }
+
+    pub fn boards(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn max_turns(&self) -> u32 {
+        turns_for_boards(self.boards())
+    }
+
+    pub fn answers(&self) -> &Vec<Equation> {
+        &self.answers
+    }
+
+    pub fn solved(&self) -> &Vec<bool> {
+        &self.solved
+    }
+
+    pub fn won(&self) -> bool {
+        self.solved.iter().all(|solved| *solved)
+    }
+
+    // Score a guess against every board.  Boards which were already solved don't get a result.
+    pub fn guess(&mut self, guess: &Equation) -> Result<Vec<Option<NerdleResult>>, NerdleError> {
+        let mut results = Vec::new();
+        for (board, answer) in self.answers.iter().enumerate() {
+            if self.solved[board] {
+                results.push(None);
+            } else {
+                results.push(Some(if self.commutative {
+                    nerdle_commutative(guess, answer)?
+                } else {
+                    nerdle(guess, answer)?
+                }));
+            }
+        }
+        for (board, res) in results.iter().enumerate() {
+            if let Some(res) = res {
+                self.solved[board] = res.won();
+            }
+        }
+        Ok(results)
+    }
+}
+
+// Plays several boards at once by keeping a separate solver for each board
+pub struct MultiNerdleSolver {
+    boards: Vec<NerdleSolver>,
+    solved: Vec<bool>,
+}
+
+impl MultiNerdleSolver {
+    pub fn new(boards: usize) -> MultiNerdleSolver {
+        MultiNerdleSolver {
+            boards: (0..boards).map(|_| NerdleSolver::new()).collect(),
+            solved: vec![false; boards],
+        }
+    }
+
+    // Each unsolved board suggests a guess, and we pick the one which could still be the answer on the most
+    // unsolved boards.  A guess which can't be right for a board still tells us about it, but one which could
+    // be might also win it outright.
+    pub fn take_guess(&self) -> Result<Equation, NoMatchFound> {
+        let mut best: Option<(usize, Equation)> = None;
+        let mut last_err = None;
+        for (board, solver) in self.boards.iter().enumerate() {
+            if self.solved[board] {
+                continue;
+            }
+            let guess = match solver.take_guess() {
+                Ok(guess) => guess,
+                Err(err) => {
+                    last_err = Some(err);
+                    continue;
+                }
+            };
+            let score = self.unsolved_boards()
+                .filter(|other| self.boards[*other].answer_ok(&guess).is_ok())
+                .count();
+            if best.as_ref().map(|(best_score, _)| score > *best_score).unwrap_or(true) {
+                best = Some((score, guess));
+            }
+        }
+        match (best, last_err) {
+            (Some((_, guess)), _) => Ok(guess),
+            (None, Some(err)) => Err(err),
+            (None, None) => Err(NoMatchFound { message: "All boards are already solved".to_string() }),
+        }
+    }
+
+    pub fn update(&mut self, guess: &Equation, results: &[Option<NerdleResult>]) {
+        for (board, res) in results.iter().enumerate() {
+            if let Some(res) = res {
+                if res.won() {
+                    self.solved[board] = true;
+                } else {
+                    self.boards[board].update(guess, res);
+                }
+            }
+        }
+    }
+
+    pub fn set_commutative(&mut self, commutative: bool) {
+        for solver in self.boards.iter_mut() {
+            solver.set_commutative(commutative);
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: RuleSet) {
+        for solver in self.boards.iter_mut() {
+            solver.set_rules(rules);
+        }
+    }
+
+    // Each board's solver state, as a JSON array in board order
+    pub fn state(&self) -> Option<String> {
+        let boards: Vec<serde_json::Value> = self.boards.iter().map(|solver| solver.to_json()).collect();
+        Some(serde_json::Value::from(boards).to_string())
+    }
+
+    fn unsolved_boards(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.boards.len()).filter(move |board| !self.solved[*board])
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn multi_game_test() {
+    let mut game = MultiNerdleGame::new(vec![
+        Equation::from_str("12+34=46").unwrap(),
+        Equation::from_str("10+20=30").unwrap(),
+    ]);
+    assert_eq!(game.max_turns(), BINERDLE_TURNS);
+
+    let results = game.guess(&Equation::from_str("10+20=30").unwrap()).unwrap();
+    assert_eq!(results[0].as_ref().unwrap().to_string(), "G-GY-GY-");
+    assert!(results[1].as_ref().unwrap().won());
+    assert!(!game.won());
+    assert_eq!(game.solved(), &vec![false, true]);
+
+    let results = game.guess(&Equation::from_str("12+34=46").unwrap()).unwrap();
+    assert!(results[0].as_ref().unwrap().won());
+    assert!(results[1].is_none());
+    assert!(game.won());
+}
+
+#[test]
+fn multi_solver_test() {
+    let mut solver = MultiNerdleSolver::new(2);
+    let guess = Equation::from_str("10+20=30").unwrap();
+    solver.update(&guess, &[Some(NerdleResult::from_str("G-GY-GY-").unwrap()), Some(NerdleResult::from_str("GGGGGGGG").unwrap())]);
+    assert_eq!(solver.unsolved_boards().collect::<Vec<usize>>(), vec![0]);
+
+    // Only the first board is left, so its guess must fit what we know about it
+    let next = solver.take_guess().unwrap();
+    assert!(solver.boards[0].answer_ok(&next).is_ok());
+}
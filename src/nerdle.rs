@@ -1,8 +1,9 @@
 use crate::eq::Equation;
 // use crate::expr;
 use std::fmt;
-use std::collections::HashMap;
 use std::str::FromStr;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use serde::de::Error;
 
 use crate::expr;
 
@@ -11,14 +12,12 @@ pub const NERDLE_TURNS: u32 = 6;
 pub const NERDLE_MAX_OPS: u32 = 2;
 
 pub const NERDLE_CHARACTERS: u32 = 8;
-pub const NERDLE_NUM_MAX: i32 = 999;
-pub const NERDLE_OPERAND_MAX_DIGITS: u32 = 4;
+pub const NERDLE_NUM_MAX: i64 = 999;
 // 10*1 to 99*9, other values won't have 10 digits
 // pub const NERDLE_C_MUL_MAX: i32 = 891;
 // pub const NERDLE_C_MUL_MIN: i32 = 100;
 // pub const NERDLE_C_OTHER_MAX: i32 = 99;
 // pub const NERDLE_C_OTHER_MIN: i32= 1;
-pub const NERDLE_A_MAX: i32 = 999; // TODO: Might even be 9999
 
 // 10-digit options
 // pub const NERDLE_CHARACTERS: i32 = 10;
@@ -46,12 +45,12 @@ pub fn nerdle_str(guess: &str, answer: &str) -> Result<NerdleResult, NerdleError
     }
     let answer = answer.as_bytes();
 
-    // First count everything up
-    let mut remaining: HashMap<u8, i32> = HashMap::new();
+    // First count everything up.  A plain array indexed by byte is much faster than a HashMap, which matters
+    // when comparing every possible guess against every possible answer.
+    let mut remaining = [0i32; 256];
 
     for &ch in answer.iter() {
-        let counter = remaining.entry(ch).or_insert(0);
-        *counter += 1;
+        remaining[ch as usize] += 1;
     }
 
     // println!("Initial Counts: {:?}", remaining);
@@ -61,7 +60,7 @@ pub fn nerdle_str(guess: &str, answer: &str) -> Result<NerdleResult, NerdleError
         let guess_pos = guess[pos];
         if guess_pos == answer[pos] {
             result.positions[pos] = NerdlePositionResult::Green;
-            remaining.entry(guess_pos).and_modify(|counter| *counter -= 1);
+            remaining[guess_pos as usize] -= 1;
         }
     }
     // println!("Remaining after green: {:?}", remaining);
@@ -71,8 +70,7 @@ pub fn nerdle_str(guess: &str, answer: &str) -> Result<NerdleResult, NerdleError
         let guess_pos = guess[pos];
         // If they are equal we handled them above
         if guess_pos != answer[pos] {
-            // TODO: or_insert shouldn't be necessary here, not sure how to simply assert it will be there
-            let counter = remaining.entry(guess_pos).or_insert(0);
+            let counter = &mut remaining[guess_pos as usize];
             // println!("At position {} guess '{}' ({}) remaining {}", pos, guess_pos as char, guess_pos, *counter);
             if *counter > 0 {
                 result.positions[pos] = NerdlePositionResult::Yellow;
@@ -98,7 +96,19 @@ pub fn nerdle(guess: &Equation, answer: &Equation) -> Result<NerdleResult, Nerdl
     nerdle_str(&guess.to_string(), &answer.to_string())
 }
 
-#[derive(Clone, Copy)]
+// Like nerdle, but following the rule that a guess which is just the answer with its terms reordered also wins
+pub fn nerdle_commutative(guess: &Equation, answer: &Equation) -> Result<NerdleResult, NerdleError> {
+    let res = nerdle(guess, answer)?;
+    if !res.won() && guess.is_commutation_of(answer) {
+        Ok(NerdleResult {
+            positions: [NerdlePositionResult::Green; NERDLE_CHARACTERS as usize],
+        })
+    } else {
+        Ok(res)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum NerdlePositionResult {
     Yellow,
     Green,
@@ -115,6 +125,7 @@ impl fmt::Display for NerdlePositionResult {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct NerdleResult {
     pub positions: [NerdlePositionResult; NERDLE_CHARACTERS as usize],
 }
@@ -170,6 +181,20 @@ impl fmt::Display for NerdleResult {
     }
 }
 
+// Serialized the way results are typed in, like "GY--G-YG"
+impl Serialize for NerdleResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NerdleResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        NerdleResult::from_str(&text).map_err(D::Error::custom)
+    }
+}
+
 #[derive(Clone)]
 pub struct NerdleError {
     pub message: String,
@@ -2,13 +2,26 @@ use std::fmt;
 use std::collections::{HashMap, HashSet};
 use std::cmp::{max};
 
+use serde::{Serialize, Deserialize};
+
 use crate::eq::Equation;
+use crate::explain::{ImpossibleReason, explain_impossible};
+use crate::rules::RuleSet;
 use crate::nerdle::{NerdleResult, NerdlePositionResult, NerdleError, NERDLE_CHARACTERS, NERDLE_VALID_CHAR_BYTES};
 
+// Everything the solver has learned, as plain data so a stuck game can be saved to JSON and loaded again
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NerdleData {
     pub char_info: HashMap<u8, NerdleCharInfo>,
     pub positions: [HashMap<u8, bool>; NERDLE_CHARACTERS as usize],
     pub equal_pos: Option<usize>,
+    // When commuted forms of the answer win, no commuted form of a losing guess can be the answer either
+    pub commutative: bool,
+    pub wrong_canonical: HashSet<String>,
+    // Every guess and result so far, for explaining why an equation is impossible
+    pub history: Vec<(Equation, NerdleResult)>,
+    #[serde(default)]
+    pub rules: RuleSet,
 }
 
 impl Default for NerdleData {
@@ -18,6 +31,10 @@ impl Default for NerdleData {
             char_info: HashMap::new(),
             positions,
             equal_pos: None,
+            commutative: false,
+            wrong_canonical: HashSet::new(),
+            history: Vec::new(),
+            rules: RuleSet::default(),
         }
     }
 }
@@ -45,6 +62,10 @@ impl NerdleData {
             }
         }
 
+        if self.commutative && self.wrong_canonical.contains(&eq.canonical()) {
+            return Err(NerdleError { message: format!("Equation {} is a commuted form of an earlier guess", eq)})
+        }
+
         // Check characters in positions
         for pos in 0..(NERDLE_CHARACTERS as usize) {
             let guess_ch = eq_bytes[pos];
@@ -76,7 +97,27 @@ impl NerdleData {
         description
     }
 
+    // All the reasons an equation is impossible, unlike eq_matches() which stops at the first
+    pub fn explain(&self, eq: &Equation) -> Vec<ImpossibleReason> {
+        let mut reasons = explain_impossible(&self.history, eq);
+        if self.commutative {
+            let canonical = eq.canonical();
+            for (turn, (guess, res)) in self.history.iter().enumerate() {
+                if !res.won() && guess.canonical() == canonical {
+                    reasons.push(ImpossibleReason { turn: Some(turn + 1), position: None,
+                        message: format!("turn {} guessed {}, which only reorders {} and didn't win", turn + 1, guess, eq) });
+                }
+            }
+        }
+        reasons
+    }
+
     pub fn update(&mut self, guess: &Equation, result: &NerdleResult) {
+        self.history.push((guess.clone(), result.clone()));
+        if self.commutative && !result.won() {
+            self.wrong_canonical.insert(guess.canonical());
+        }
+
         // let mut state = ParseState::InA;
         let guess_str = guess.to_string();
         let guess = guess_str.as_bytes();
@@ -195,7 +236,7 @@ impl NerdleData {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NerdleCharInfo {
     pub min_count: u32,
     pub max_count: u32,
@@ -218,7 +259,7 @@ impl fmt::Display for NerdleCharInfo {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum NerdleIsChar {
     Definitely,
     DefinitelyNot,
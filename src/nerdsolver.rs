@@ -4,14 +4,17 @@ use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::cmp::{min, max};
 use regex::Regex;
+use serde_json::{json, Value};
 
 use crate::strategy::Strategy;
 use crate::eq::Equation;
-use crate::nerdle::{NerdleResult, NerdleError, NERDLE_CHARACTERS, NERDLE_VALID_CHAR_BYTES, NERDLE_OPERAND_MAX_DIGITS, NERDLE_MAX_OPS};
+use crate::nerdle::{NerdleResult, NerdleError, NERDLE_CHARACTERS, NERDLE_VALID_CHAR_BYTES, NERDLE_MAX_OPS};
 use crate::eqgen::{eqgen_constrained};
-use crate::constraint::{EquationConstraint, ExpressionNumberConstraint, NoMatchFound, range_for_digits, range_for_digits_or_less};
-use crate::expr::{ExpressionNumber};
+use crate::constraint::{EquationConstraint, ExpressionNumberConstraint, DigitPattern, NoMatchFound, range_for_digits};
+use crate::layout::layouts;
 use crate::nerdledata::{NerdleData, NerdleCharInfo, NerdleIsChar};
+use crate::explain::ImpossibleReason;
+use crate::rules::RuleSet;
 
 const OPERATOR_STR: &str = "-+*/";
 
@@ -22,16 +25,12 @@ pub struct NerdleSolver {
 impl Strategy for NerdleSolver {
     fn take_guess(&self) -> Result<Equation, NoMatchFound> {
         let constraint = self.constraint();
-        println!("Constraint: {}", &constraint);
+        // Diagnostics go to stderr so stdout stays clean for the bot protocol
+        eprintln!("Constraint: {}", &constraint);
 
-        let mut r = eqgen_constrained(&constraint);
-        for _ in 0..100 {
-            if r.is_ok() {
-                return r;
-            }
-            r = eqgen_constrained(&constraint);
-        }
-        r
+        // The search is exhaustive, so failing to find anything means nothing fits
+        eqgen_constrained(&constraint)
+            .map_err(|_| NoMatchFound { message: "No answers fit the results so far".to_string() })
     }
 
     fn update(&mut self, guess: &Equation, result: &NerdleResult) {
@@ -85,6 +84,18 @@ impl Strategy for NerdleSolver {
             }
             print!("\n");
         }
+
+        let answers: Vec<Equation> = self.constraint().iter().collect();
+        match answers.len() {
+            0 => println!("No answers fit the results so far"),
+            count if count <= 10 => {
+                println!("{} possible answers:", count);
+                for eq in answers.iter() {
+                    println!("    {}", eq);
+                }
+            },
+            count => println!("{} possible answers", count),
+        }
     }
 
     fn answer_ok(&self, eq: &Equation) -> Result<(), NerdleError> {
@@ -96,6 +107,29 @@ impl Strategy for NerdleSolver {
 
         Ok(())
     }
+
+    fn explain(&self, eq: &Equation) -> Vec<ImpossibleReason> {
+        let mut reasons = self.data.borrow().explain(eq);
+        // The constraint can also rule things out that no single result does, like too many operators
+        if reasons.is_empty() {
+            if let Err(err) = self.answer_ok(eq) {
+                reasons.push(ImpossibleReason { turn: None, position: None, message: err.message });
+            }
+        }
+        reasons
+    }
+
+    fn set_commutative(&mut self, commutative: bool) {
+        self.data.borrow_mut().commutative = commutative;
+    }
+
+    fn set_rules(&mut self, rules: RuleSet) {
+        self.data.borrow_mut().rules = rules;
+    }
+
+    fn state(&self) -> Option<String> {
+        Some(self.to_json().to_string())
+    }
 }
 
 impl NerdleSolver {
@@ -105,21 +139,38 @@ impl NerdleSolver {
         }
     }
 
+    // Everything learned so far, with the constraint it leads to for reference.  from_json() only needs the data.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "data": &*self.data.borrow(),
+            "constraint": self.constraint(),
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<NerdleSolver, NerdleError> {
+        let state: Value = serde_json::from_str(json)
+            .map_err(|err| NerdleError { message: format!("Invalid solver state: {}", err)})?;
+        let data: NerdleData = serde_json::from_value(state["data"].clone())
+            .map_err(|err| NerdleError { message: format!("Invalid solver data: {}", err)})?;
+        Ok(NerdleSolver {
+            data: Rc::new(RefCell::new(data)),
+        })
+    }
+
     pub fn constraint(&self) -> EquationConstraint {
         let mut constraint = EquationConstraint {
-            accept: {
-                let data: Rc<RefCell<NerdleData>> = self.data.clone();
-                Rc::new(move |eq| {
-                    match data.borrow().eq_matches(&eq) {
-                        Ok(()) => true,
-                        Err(_e) => {
-                            // println!("  Equation {} not possible because {}", eq, e);
-                            false
-                        }
-                    }
-                })
+            positions: (0..NERDLE_CHARACTERS as usize).map(|pos| {
+                let data = self.data.borrow();
+                self.possibilities_for_pos(pos).into_iter().filter(|ch| data.positions[pos].get(ch) != Some(&false)).collect()
+            }).collect(),
+            counts: self.data.borrow().char_info.iter().map(|(ch, info)| (*ch, info.min_count..=info.max_count)).collect(),
+            excluded_canonical: if self.data.borrow().commutative {
+                self.data.borrow().wrong_canonical.clone()
+            } else {
+                HashSet::new()
             },
             accept_description: self.data.borrow().describe_counts(),
+            rules: self.data.borrow().rules,
             ..Default::default()
         };
 
@@ -131,232 +182,91 @@ impl NerdleSolver {
             match data.char_info.get(op) {
                 Some(info) => {
                     let max_count = min(info.max_count, NERDLE_MAX_OPS);
-                    min_ops += info.min_count;
+                    // One minus might be the sign of a negative result rather than an operator
+                    let min_count = if *op == b'-' && data.rules.negative_results {
+                        info.min_count.saturating_sub(1)
+                    } else {
+                        info.min_count
+                    };
+                    let min_count = min(min_count, max_count);
+                    min_ops += min_count;
                     max_ops += max_count;
-                    constraint.operator.insert(*op, info.min_count..=max_count);
+                    constraint.operator.insert(*op, min_count..=max_count);
                 }
                 None => { }
             }
         }
         constraint.num_ops = max(min_ops, 1)..=min(max_ops, NERDLE_MAX_OPS);
 
-        match data.equal_pos {
-            Some(pos) => {
-                let digits = NERDLE_CHARACTERS as usize - pos - 1;
-                let range = range_for_digits(digits, true);
-                let description = format!("Updating c_range to {}..={} because = is in pos {} leaving {} digits", range.start(), range.end(), pos, digits);
-                constraint.c_constraint = ExpressionNumberConstraint {
-                    range,
-                    description,
-                    ..Default::default()
-                };
-            },
-            _ => {}
-        };
-
-        let is_op_at = |pos: usize| -> bool {
-            data.possibilities_for_pos(pos)
-                .iter()
-                .all(|ch| NerdleSolver::is_op_char(*ch as char))
-        };
-
-        let op1_pos_opt = (0..NERDLE_CHARACTERS as usize).find(|i| is_op_at(*i as usize));
-        let op2_pos_opt = match op1_pos_opt {
-            Some(op1_pos) => ((op1_pos+1)..NERDLE_CHARACTERS as usize).find(|i| is_op_at(*i as usize)),
-            None => None
-        };
-
-        let max_digits = NERDLE_OPERAND_MAX_DIGITS as usize;
-        // println!("Pattern check: ({}, {}, {})", op1_pos_opt.unwrap_or(99), op2_pos_opt.unwrap_or(99), data.equal_pos.unwrap_or(99));
-        match (op1_pos_opt, op2_pos_opt, data.equal_pos) {
-            (Some(op1_pos), Some(op2_pos), Some(equal_pos)) => {
-                // println!("Pattern 1: op1_pos={}, op2_pos={}, equal_pos={}", op1_pos, op2_pos, equal_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, false, false, "a");
-                constraint.b_constraint = self.constraint_for_digits_start_end(op1_pos, op2_pos, false, false, "b");
-                constraint.b2_constraint = self.constraint_for_digits_start_end(op2_pos, equal_pos, false, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits_start_end(equal_pos, NERDLE_CHARACTERS as usize, false, true, "c");
-                constraint.num_ops = 2..=2;
-            },
-            (Some(op1_pos), Some(op2_pos), None) => {
-                // println!("Pattern 2: op1_pos={}, op2_pos={}", op1_pos, op2_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, false, false, "a");
-                constraint.b_constraint = self.constraint_for_digits_start_end(op1_pos, op2_pos, false, false, "b");
-                constraint.num_ops = 2..=2;
-            },
-            (Some(op1_pos), None, Some(equal_pos)) if op1_pos < 3 && (equal_pos - op1_pos) <= 3 => {
-                // (equal_pos - p1_pos) < 3, must be just one op
-                // println!("Pattern 3b: op1_pos={}, equal_pos={}", op1_pos, equal_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, false, false, "a");
-                constraint.b_constraint = self.constraint_for_digits_start_end(op1_pos, equal_pos, false, false, "b");
-                constraint.c_constraint = self.constraint_for_digits_start_end(equal_pos, NERDLE_CHARACTERS as usize, false, true, "c");
-                constraint.num_ops = 1..=1;
-            },
-            (Some(op1_pos), _, Some(equal_pos)) if op1_pos < 3 => {
-                // op1_pos < 3, we know there is not another operator before op1_pos
-                // println!("Pattern 3: op1_pos={}, equal_pos={}", op1_pos, equal_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, false, false, "a");
-                constraint.b_constraint = self.constraint_for_digits_start_end(op1_pos, equal_pos, true, false, "b");
-                // constraint.b2_constraint = self.constraint_for_digits_or_less(op_equal_pos - op1_pos - 1, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits_start_end(equal_pos, NERDLE_CHARACTERS as usize, false, true, "c");
-            },
-            (Some(op1_pos), None, Some(equal_pos)) => {
-                // op1_pos >= 3, there may or may not be another operator before op1_pos
-                // max digits for b and b2 is 2, possibilities are:
-                //    v Position 3
-                //     v Position 4 - Can't be after this, not enough room for =
-                // a+b+B=cc
-                // a+bb+B=c
-                // aaa-bb=c
-                // a+b+BB=c
-                // println!("Pattern 3a: op1_pos={}, equal_pos={}", op1_pos, equal_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, true, false, "a");
-                constraint.b_constraint = self.constraint_for_digits(2, None, true, false, "b");
-                constraint.b2_constraint = self.constraint_for_digits(2, None, true, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits_start_end(equal_pos, NERDLE_CHARACTERS as usize, false, true, "c");
-            },
-            (Some(op1_pos), _, _) => {
-                let a_must_be_before_op1 = op1_pos < 3;
-                // println!("Pattern 4: op1_pos={}, a_must_be_before_op1={}", op1_pos, a_must_be_before_op1);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, op1_pos, !a_must_be_before_op1, false, "a");
-                constraint.b_constraint = self.constraint_for_digits(max_digits, None, true, false, "b");
-                constraint.b2_constraint = self.constraint_for_digits(max_digits, None, true, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits(max_digits, None, true, true, "c");
-            },
-            (_, _, Some(equal_pos)) => {
-                // println!("Pattern 90: equal_pos = {}", equal_pos);
-                constraint.a_constraint = self.constraint_for_digits_start_end(0, min(equal_pos, max_digits), true, false, "a");
-                constraint.b_constraint = self.constraint_for_digits(max_digits, None, true, false, "b");
-                constraint.b2_constraint = self.constraint_for_digits(max_digits, None, true, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits_start_end(equal_pos, NERDLE_CHARACTERS as usize, false, true, "c");
-            },
-            // TODO: Lots more combinations
+        // Every way the numbers could be laid out, each number is limited to what fits in some layout
+        let layouts = layouts(&constraint);
+        match (layouts.iter().map(|layout| layout.num_ops()).min(), layouts.iter().map(|layout| layout.num_ops()).max()) {
+            (Some(min_ops), Some(max_ops)) => constraint.num_ops = min_ops as u32..=max_ops as u32,
             _ => {
-                // println!("Pattern 99");
-                constraint.a_constraint = self.constraint_for_digits(max_digits, Some(0), true, false, "a");
-                constraint.b_constraint = self.constraint_for_digits(max_digits, None, true, false, "b");
-                constraint.b2_constraint = self.constraint_for_digits(max_digits, None, true, false, "b2");
-                constraint.c_constraint = self.constraint_for_digits(max_digits, None, true, true, "c");
+                constraint.accept_description = format!("no layout fits, {}", constraint.accept_description);
+                return constraint;
             }
         }
+        let max_operands = layouts.iter().map(|layout| layout.operands.len()).max().unwrap_or(0);
+        constraint.operands = (0..max_operands).map(|i| {
+            let spans = layouts.iter()
+                .filter(|layout| layout.operands.len() > i)
+                .map(|layout| (layout.operand_starts()[i], layout.operands[i], false))
+                .collect();
+            NerdleSolver::constraint_for_spans(&constraint.positions, spans, constraint.rules.zero_operands, &format!("operand {}", i + 1))
+        }).collect();
+        let spans = layouts.iter().map(|layout| (layout.result_start(), layout.result, layout.negative_result)).collect();
+        constraint.c_constraint = NerdleSolver::constraint_for_spans(&constraint.positions, spans, constraint.rules.zero_results, "c");
 
         constraint
     }
 
-    fn is_op_char(ch: char) -> bool {
-        match ch {
-            '+' | '-' | '/' | '*' => true,
-            _ => false
-        }
-    }
-
-    fn possibilities_for_pos(&self, pos: usize) -> HashSet<u8> {
-        self.data.borrow().possibilities_for_pos(pos)
-    }
-
-    fn constraint_for_digits_start_end(&self, start: usize, end: usize, min: bool, allow_zero: bool, name: &str) -> ExpressionNumberConstraint {
-        // println!("constraint_for_digits_start_end(&self, {}, {}, {}, {})", &start, &end, &min, &name);
-        let (start, digits) = if start == 0 {
-            (0, end)
-        } else {
-            (start + 1, end - start - 1)
-        };
-
-        self.constraint_for_digits(digits, Some(start), min, allow_zero, name)
-    }
-
-    fn constraint_for_digits(&self, digits: usize, start: Option<usize>, min: bool, allow_zero: bool, name: &str) -> ExpressionNumberConstraint {
-        // println!("Finding constraints for {}", &name);
-        let range = if min {
-            range_for_digits_or_less(digits, allow_zero)
+    // A number which could be any of the spans, given as (start, digits, negative), using the characters positions
+    // allows.  The start is that of the first digit, after any minus sign.
+    fn constraint_for_spans(positions: &[HashSet<u8>], mut spans: Vec<(usize, usize, bool)>, allow_zero: bool, name: &str) -> ExpressionNumberConstraint {
+        spans.sort_unstable();
+        spans.dedup();
+        let span_range = |digits: usize, negative: bool| if negative {
+            let range = range_for_digits(digits, false);
+            -range.end()..=-range.start()
         } else {
             range_for_digits(digits, allow_zero)
         };
-        let regex = match start {
-            Some(start) => self.regex_for_digits_at(start, digits, min),
-            None => self.regex_for_digits_anywhere(digits, min),
-        };
-        let description = format!("{} has {} {} digits range {}..={} regex /{}/",
+        let start = spans.iter().map(|(_, digits, negative)| *span_range(*digits, *negative).start()).min().unwrap_or(0);
+        let end = spans.iter().map(|(_, digits, negative)| *span_range(*digits, *negative).end()).max().unwrap_or(0);
+        let range = start..=end;
+
+        let alternatives: Vec<String> = spans.iter().map(|(start, digits, negative)| {
+            let sign = if *negative { "-" } else { "" };
+            sign.to_string() + &(*start..start + digits).map(|pos| {
+                let mut allowed: Vec<char> = positions[pos].iter()
+                    .map(|byte| *byte as char)
+                    .filter(|chr| chr.is_ascii_digit())
+                    .collect();
+                allowed.sort_unstable();
+                format!("[{}]", allowed.into_iter().collect::<String>())
+            }).collect::<String>()
+        }).collect();
+        // TODO: Better error handling?
+        let regex = Regex::new(&format!("(?-u)^(?:{})$", alternatives.join("|"))).unwrap();
+
+        let description = format!("{} has {} range {}..={} regex /{}/",
             &name,
-            if min {
-                &"up to"
-            } else {
-                &"exactly"
-            },
-            &digits,
+            spans.iter()
+                .map(|(start, digits, negative)| format!("{}{} digits at {}", if *negative { "-" } else { "" }, digits, start))
+                .collect::<Vec<String>>().join(" or "),
             &range.start(), &range.end(),
             &regex.as_str());
-        let accept = Rc::new(move |n: &ExpressionNumber| {
-            // println!("Checking {} against regex {}", &n, regex);
-            regex.is_match(&n.to_string())
-        });
         ExpressionNumberConstraint {
             range,
             description,
-            accept,
-            ..Default::default()
+            pattern: Some(DigitPattern(regex)),
         }
     }
 
-    fn regex_for_digits_at(&self, start: usize, digits: usize, min: bool) -> Regex {
-        // println!("regex_for_digits_at(&self, {}, {}, {})", &start, &digits, &min);
-        let mut regex = String::new();
-        regex.push_str("(?-u)^");
-        for pos in start..(start+digits) {
-            if min && pos != start {
-                regex.push_str("(?:|");
-            }
-            regex.push_str("[");
-            for byte in self.possibilities_for_pos(pos).iter() {
-                let chr = *byte as char;
-                match chr {
-                    '0'..='9' => regex.push(chr),
-                    _ => { }
-                }
-            }
-            regex.push_str("]");
-        }
-
-        if min {
-            for _pos in (start+1)..(start+digits) {
-                regex.push_str(")");
-            }
-        }
-        regex.push_str("$");
-
-        // TODO: Better error handling?
-        Regex::new(&regex).unwrap()
-    }
-
-    fn regex_for_digits_anywhere(&self, digits: usize, min: bool) -> Regex {
-        let data = self.data.borrow();
-        let mut regex = String::new();
-        regex.push_str("(?-u)^[");
-
-        for chr in '0'..='9' {
-            let byte = chr as u8;
-            match data.char_info.get(&byte) {
-                None => {
-                    regex.push(chr);
-                },
-                Some(info) if info.max_count > 0 => {
-                    regex.push(chr);
-                },
-                _ => { }
-            }
-        }
-
-        regex.push_str("]{");
-        if min {
-            regex.push_str(&format!("1,{}", digits));
-        } else {
-            regex.push_str(&format!("{}", digits));
-        }
-        regex.push_str("}$");
-
-        // TODO: Better error handling?
-        Regex::new(&regex).unwrap()
+    fn possibilities_for_pos(&self, pos: usize) -> HashSet<u8> {
+        self.data.borrow().possibilities_for_pos(pos)
     }
-
 }
 
 impl fmt::Display for NerdleSolver {
@@ -398,17 +308,88 @@ fn regression_test_1() {
 }
 
 #[test]
-fn regex_for_digits_test_1() {
+fn operand_constraint_test() {
     let mut solver = NerdleSolver::new();
     solver.update(&Equation::from_str("62+28=90").unwrap(), &NerdleResult::from_str("YG-YYY--").unwrap());
-    let regex = solver.regex_for_digits_at(0, 4, true);
+    let constraint = solver.constraint();
+    let regex = &constraint.operands[0].pattern.as_ref().unwrap().0;
     println!("Regex: {}", regex);
     assert!(!regex.is_match("23"));
     assert!(!regex.is_match("6"));
     assert!(!regex.is_match("41"));
+    // Position 1 is known to be 2, so the first number has at least two digits
+    assert!(!regex.is_match("3"));
 
-    assert!(regex.is_match("3"));
     assert!(regex.is_match("32"));
     assert!(regex.is_match("321"));
-    assert!(regex.is_match("3217"));
+    assert!(constraint.operands[0].description.contains("2 digits at 0"));
+}
+
+#[test]
+fn commutative_test() {
+    let mut solver = NerdleSolver::new();
+    solver.set_commutative(true);
+    solver.update(&Equation::from_str("20+30=50").unwrap(), &NerdleResult::from_str("YGGYGGGG").unwrap());
+    assert!(solver.answer_ok(&Equation::from_str("30+20=50").unwrap()).is_err());
+
+    // Without the commutative rule, the commuted form could still be the answer
+    let mut solver = NerdleSolver::new();
+    solver.update(&Equation::from_str("20+30=50").unwrap(), &NerdleResult::from_str("YGGYGGGG").unwrap());
+    assert!(solver.answer_ok(&Equation::from_str("30+20=50").unwrap()).is_ok());
+}
+
+#[test]
+fn explain_test() {
+    let mut solver = NerdleSolver::new();
+    solver.set_commutative(true);
+    solver.update(&Equation::from_str("10+20=30").unwrap(), &NerdleResult::from_str("-GGYGGYG").unwrap());
+    assert!(solver.explain(&Equation::from_str("20+30=50").unwrap()).is_empty());
+
+    let reasons = solver.explain(&Equation::from_str("20+10=30").unwrap());
+    assert!(reasons.iter().all(|reason| reason.turn == Some(1)));
+    assert!(reasons.iter().any(|reason| reason.message == "turn 1 position 1 was gray for 1, so there is no 1, but 20+10=30 has 1"));
+    assert!(reasons.iter().any(|reason| reason.message == "turn 1 guessed 10+20=30, which only reorders 20+10=30 and didn't win"));
+}
+
+#[test]
+fn no_answers_test() {
+    let mut solver = NerdleSolver::new();
+    // Every equation has an equal sign, so no answer can fit
+    solver.update(&Equation::from_str("12+34=46").unwrap(), &NerdleResult::from_str("--------").unwrap());
+    assert!(!solver.constraint().is_satisfiable());
+    assert_eq!(solver.take_guess().err().unwrap().message, "No answers fit the results so far");
+}
+
+#[test]
+fn state_json_test() {
+    let mut solver = NerdleSolver::new();
+    solver.set_commutative(true);
+    solver.update(&Equation::from_str("10+20=30").unwrap(), &NerdleResult::from_str("-GGYGGYG").unwrap());
+    let json = solver.to_json().to_string();
+
+    let loaded = NerdleSolver::from_json(&json).unwrap();
+    assert_eq!(loaded.constraint().count(), solver.constraint().count());
+    assert_eq!(loaded.take_guess().unwrap().to_string(), solver.take_guess().unwrap().to_string());
+    assert!(loaded.answer_ok(&Equation::from_str("20+10=30").unwrap()).is_err());
+
+    // The constraint on its own works after a round trip too, digit patterns and excluded equations included
+    let constraint: EquationConstraint = serde_json::from_value(solver.to_json()["constraint"].clone()).unwrap();
+    assert_eq!(constraint.count(), solver.constraint().count());
+    assert!(constraint.accept(&Equation::from_str("20+10=30").unwrap()).is_err());
+    assert!(NerdleSolver::from_json("{\"data\": 5}").is_err());
+}
+
+#[test]
+fn rules_test() {
+    let eq = Equation::from_str("12-13=-1").unwrap();
+    let mut solver = NerdleSolver::new();
+    assert!(solver.answer_ok(&eq).is_err());
+
+    solver.set_rules(RuleSet::from_str("negative_results").unwrap());
+    assert!(solver.answer_ok(&eq).is_ok());
+    let guess = Equation::from_str("12+34=46").unwrap();
+    solver.update(&guess, &crate::nerdle::nerdle(&guess, &eq).unwrap());
+    assert!(solver.answer_ok(&eq).is_ok());
+    assert!(solver.constraint().iter().any(|answer| answer.to_string() == "12-13=-1"));
+    assert!(solver.take_guess().is_ok());
 }
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::eq::Equation;
+use crate::host::{NerdleHost, FixedAnswerHost};
+use crate::nerdle::{nerdle_str, NerdleError, NerdlePositionResult, NERDLE_CHARACTERS, NERDLE_TURNS};
+use crate::strategy::{Strategy, StrategyEnum};
+
+// Rates how hard an answer is to find.  Nothing here is exact: the rating combines how a reference strategy
+// actually does against the answer with features that have tripped it up before.
+
+// Answers the solver has lost on, from the Losses section of NOTES.md.  Most are NN+NN=NN or NN-NN=NN with
+// lots of near-identical neighbors, where there are more candidates left than turns to try them.
+pub const KNOWN_TRAPS: &[&str] = &[
+    "51+30=81", "32+10=42", "10+62=72", "35-15=20", "77-17=60", "11+40=51",
+    "36+50=86", "40+41=81", "26+30=56", "89-19=70", "25+30=55", "35+30=65",
+];
+
+// Answers which would score this many greens or more as a guess are easy to mix up with it
+const NEIGHBOR_GREENS: usize = NERDLE_CHARACTERS as usize - 2;
+
+pub struct EquationRating {
+    pub equation: String,
+    // Average turns for the reference strategy to win, counting a loss as one more than the turn limit
+    pub expected_turns: Option<f64>,
+    pub losses: usize,
+    pub neighbors: Vec<String>,
+    pub repeated_digits: usize,
+    pub layout: Vec<String>,
+    pub known_trap: bool,
+}
+
+impl EquationRating {
+    // One number to sort answers by; higher is harder
+    pub fn score(&self) -> f64 {
+        self.expected_turns.unwrap_or(0.0)
+            + (1.0 + self.neighbors.len() as f64).ln()
+            + 0.5 * self.repeated_digits as f64
+            + 0.5 * self.layout.len() as f64
+            + if self.known_trap { 1.0 } else { 0.0 }
+    }
+}
+
+impl fmt::Display for EquationRating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Equation: {}", self.equation)?;
+        writeln!(f, "    Score: {:.2}", self.score())?;
+        match self.expected_turns {
+            Some(turns) => writeln!(f, "    Expected turns: {:.2} ({} losses)", turns, self.losses)?,
+            None => writeln!(f, "    Expected turns: not simulated")?,
+        }
+        writeln!(f, "    Confusable neighbors: {}", self.neighbors.len())?;
+        for neighbor in self.neighbors.iter().take(10) {
+            writeln!(f, "        {}", neighbor)?;
+        }
+        if self.neighbors.len() > 10 {
+            writeln!(f, "        ...")?;
+        }
+        writeln!(f, "    Repeated digits: {}", self.repeated_digits)?;
+        writeln!(f, "    Unusual layout: {}", if self.layout.is_empty() { "none".to_string() } else { self.layout.join(", ") })?;
+        write!(f, "    Known trap: {}", if self.known_trap { "yes" } else { "no" })
+    }
+}
+
+// Rate an equation against a list of possible answers, playing `samples` games with the named strategy
+pub fn rate(eq: &Equation, answers: &[String], strategy_name: &str, samples: usize) -> Result<EquationRating, NerdleError> {
+    let equation = eq.to_string();
+
+    let mut losses = 0;
+    let mut total_turns = 0;
+    for _ in 0..samples {
+        match turns_to_win(eq, strategy_name)? {
+            Some(turns) => total_turns += turns,
+            None => {
+                losses += 1;
+                total_turns += NERDLE_TURNS + 1;
+            },
+        }
+    }
+
+    Ok(EquationRating {
+        expected_turns: if samples > 0 { Some(total_turns as f64 / samples as f64) } else { None },
+        losses,
+        neighbors: neighbors(&equation, answers)?,
+        repeated_digits: repeated_digits(&equation),
+        layout: unusual_layout(eq),
+        known_trap: KNOWN_TRAPS.contains(&equation.as_str()),
+        equation,
+    })
+}
+
+fn turns_to_win(answer: &Equation, strategy_name: &str) -> Result<Option<u32>, NerdleError> {
+    let mut solver = StrategyEnum::by_name(strategy_name)
+        .map_err(|err| NerdleError { message: format!("{}", err)})?;
+    let mut host = FixedAnswerHost::new(answer.clone());
+    for turn in 1..=NERDLE_TURNS {
+        let guess = match solver.take_guess() {
+            Ok(guess) => guess,
+            // The strategy giving up is as good as a loss
+            Err(_) => return Ok(None),
+        };
+        let res = host.respond(&guess)?;
+        if res.won() {
+            return Ok(Some(turn));
+        }
+        solver.update(&guess, &res);
+    }
+    Ok(None)
+}
+
+// Other answers which would score nearly all green as a guess for this one
+fn neighbors(equation: &str, answers: &[String]) -> Result<Vec<String>, NerdleError> {
+    let mut neighbors = Vec::new();
+    for answer in answers.iter().filter(|answer| answer.as_str() != equation && answer.len() == equation.len()) {
+        let res = nerdle_str(answer, equation)?;
+        let greens = res.positions.iter().filter(|pos| **pos == NerdlePositionResult::Green).count();
+        if greens >= NEIGHBOR_GREENS {
+            neighbors.push(answer.clone());
+        }
+    }
+    Ok(neighbors)
+}
+
+// Digits appearing more than once, counting each extra appearance
+fn repeated_digits(equation: &str) -> usize {
+    let digits: Vec<char> = equation.chars().filter(|ch| ch.is_ascii_digit()).collect();
+    let unique: HashSet<&char> = digits.iter().collect();
+    digits.len() - unique.len()
+}
+
+fn unusual_layout(eq: &Equation) -> Vec<String> {
+    let parts: Vec<String> = eq.expr.parts.iter().map(|part| part.to_string()).collect();
+    let ops: Vec<&str> = parts.iter().skip(1).step_by(2).map(|op| op.as_str()).collect();
+    let mut layout = Vec::new();
+    if ops.len() > 1 {
+        layout.push(format!("{} operators", ops.len()));
+    }
+    if ops.iter().any(|op| *op == "*" || *op == "/") && ops.iter().any(|op| *op == "+" || *op == "-") {
+        layout.push("mixed precedence".to_string());
+    }
+    for (i, op) in ops.iter().enumerate() {
+        if *op == "/" && parts[2 * i + 2].len() >= 3 {
+            layout.push(format!("{}-digit divisor", parts[2 * i + 2].len()));
+        }
+    }
+    if eq.res.to_string().len() == 1 {
+        layout.push("1-digit result".to_string());
+    }
+    layout
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn rate_features_test() {
+    let answers: Vec<String> = ["51+30=81", "52+30=82", "51+20=71", "41+30=71", "12+34=46", "168/21=8"]
+        .iter().map(|eq| eq.to_string()).collect();
+    let rating = rate(&Equation::from_str("51+30=81").unwrap(), &answers, "first_possible", 0).unwrap();
+    assert!(rating.known_trap);
+    assert_eq!(rating.expected_turns, None);
+    assert_eq!(rating.neighbors, vec!["52+30=82", "51+20=71", "41+30=71"]);
+    assert_eq!(rating.repeated_digits, 1);
+    assert!(rating.layout.is_empty());
+
+    let rating = rate(&Equation::from_str("168/21=8").unwrap(), &answers, "first_possible", 0).unwrap();
+    assert!(!rating.known_trap);
+    assert!(rating.neighbors.is_empty());
+    assert_eq!(rating.layout, vec!["1-digit result"]);
+}
+
+#[test]
+fn unusual_layout_test() {
+    assert_eq!(unusual_layout(&Equation::from_str("9*9-2=79").unwrap()), vec!["2 operators", "mixed precedence"]);
+    assert_eq!(unusual_layout(&Equation::from_str("600/100=6").unwrap()), vec!["3-digit divisor", "1-digit result"]);
+}
+
+#[test]
+fn rate_simulated_test() {
+    let answers = vec!["12+34=46".to_string()];
+    let rating = rate(&Equation::from_str("12+34=46").unwrap(), &answers, "first_possible", 1).unwrap();
+    let turns = rating.expected_turns.unwrap();
+    assert!(turns >= 1.0 && turns <= (NERDLE_TURNS + 1) as f64);
+    assert!(rating.score() >= turns);
+}
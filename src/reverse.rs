@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::nerdle::{nerdle_str, NerdleResult, NerdlePositionResult, NERDLE_CHARACTERS};
+
+// Works backwards from a grid of results (like a share grid) with no guesses, to find which answers
+// and guesses could have produced it.
+
+// Possible guesses for one row, indexed by the characters in the row's green positions.  Any guess producing
+// this row must have exactly the answer's characters there, which narrows down the search a lot.
+struct RowGuesses<'a> {
+    row: &'a NerdleResult,
+    green_positions: Vec<usize>,
+    by_greens: HashMap<Vec<u8>, Vec<&'a String>>,
+}
+
+impl<'a> RowGuesses<'a> {
+    fn new(row: &'a NerdleResult, guesses: &'a [String]) -> RowGuesses<'a> {
+        let green_positions: Vec<usize> = (0..NERDLE_CHARACTERS as usize)
+            .filter(|pos| row.positions[*pos] == NerdlePositionResult::Green)
+            .collect();
+        let mut by_greens: HashMap<Vec<u8>, Vec<&'a String>> = HashMap::new();
+        for guess in guesses.iter() {
+            by_greens.entry(RowGuesses::key(&green_positions, guess)).or_default().push(guess);
+        }
+        RowGuesses {
+            row,
+            green_positions,
+            by_greens,
+        }
+    }
+
+    fn key(green_positions: &[usize], eq: &str) -> Vec<u8> {
+        let bytes = eq.as_bytes();
+        green_positions.iter().map(|pos| bytes[*pos]).collect()
+    }
+
+    fn matching(&self, answer: &'a str) -> impl Iterator<Item = &'a String> + '_ {
+        self.by_greens.get(&RowGuesses::key(&self.green_positions, answer))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(move |guess| nerdle_str(guess, answer).map(|res| &res == self.row).unwrap_or(false))
+    }
+}
+
+// Answers for which every row could have been produced by some guess
+pub fn possible_answers(rows: &[NerdleResult], answers: &[String], guesses: &[String]) -> Vec<String> {
+    let row_guesses: Vec<RowGuesses> = rows.iter().map(|row| RowGuesses::new(row, guesses)).collect();
+    answers.iter()
+        .filter(|answer| row_guesses.iter().all(|row| row.matching(answer).next().is_some()))
+        .cloned()
+        .collect()
+}
+
+// For a given answer, the guesses that could have been made on each turn.  Any combination of these is a
+// possible sequence of guesses.
+pub fn possible_guesses(rows: &[NerdleResult], answer: &str, guesses: &[String]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| RowGuesses::new(row, guesses).matching(answer).cloned().collect())
+        .collect()
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn possible_answers_test() {
+    let equations: Vec<String> = ["12+34=46", "10+20=30", "43-21=22", "20+30=50"].iter().map(|eq| eq.to_string()).collect();
+    let rows = vec![
+        nerdle_str("10+20=30", "20+30=50").unwrap(),
+        NerdleResult::from_str("GGGGGGGG").unwrap(),
+    ];
+    let answers = possible_answers(&rows, &equations, &equations);
+    assert!(answers.contains(&"20+30=50".to_string()));
+    for answer in answers.iter() {
+        let turns = possible_guesses(&rows, answer, &equations);
+        assert!(!turns[0].is_empty());
+        assert_eq!(turns[1], vec![answer.clone()]);
+    }
+}
+
+#[test]
+fn possible_guesses_test() {
+    let equations: Vec<String> = ["10+20=30", "168/21=8", "20+30=50"].iter().map(|eq| eq.to_string()).collect();
+    let rows = vec![nerdle_str("10+20=30", "20+30=50").unwrap()];
+    let turns = possible_guesses(&rows, "20+30=50", &equations);
+    assert_eq!(turns.len(), 1);
+    assert!(turns[0].contains(&"10+20=30".to_string()));
+    assert!(!turns[0].contains(&"168/21=8".to_string()));
+}
+
+#[test]
+fn impossible_grid_test() {
+    let equations: Vec<String> = ["12+34=46", "10+20=30", "43-21=22", "168/21=8", "9*12=108"].iter().map(|eq| eq.to_string()).collect();
+    // Every character is in the answer but none are in the right place; none of our equations can do that
+    let rows = vec![NerdleResult::from_str("YYYYYYYY").unwrap()];
+    assert!(possible_answers(&rows, &equations, &equations).is_empty());
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Deserialize};
+
+use crate::eq::Equation;
+use crate::expr::{ExpressionNumber, ExpressionPart};
+use crate::nerdle::NerdleError;
+
+// Which equations count as valid, beyond computing.  The default is classic Nerdle's: no operand can be 0, the
+// result can be 0 but not negative, and the calculation can go negative or through fractions on the way, like
+// 1-9+10=2, 3/2*4=6 or 1/2+1/2=1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    // Operands can be 0, like 0+12=12
+    pub zero_operands: bool,
+    // The result can be 0, like 12-12=0
+    pub zero_results: bool,
+    // Running totals before the result can be negative, like the 1-9 in 1-9+10=2
+    pub negative_intermediates: bool,
+    // The result can be negative, like 1-12=-11
+    pub negative_results: bool,
+    // The calculation can go through fractions, like the 3/2 in 3/2*4=6 or the halves in 1/2+1/2=1
+    pub fractions: bool,
+}
+
+const RULE_NAMES: &[&str] = &["zero_operands", "zero_results", "negative_intermediates", "negative_results", "fractions"];
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            zero_operands: false,
+            zero_results: true,
+            negative_intermediates: true,
+            negative_results: false,
+            fractions: true,
+        }
+    }
+}
+
+impl RuleSet {
+    fn rule_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "zero_operands" => Some(&mut self.zero_operands),
+            "zero_results" => Some(&mut self.zero_results),
+            "negative_intermediates" => Some(&mut self.negative_intermediates),
+            "negative_results" => Some(&mut self.negative_results),
+            "fractions" => Some(&mut self.fractions),
+            _ => None,
+        }
+    }
+
+    fn rule(&self, name: &str) -> bool {
+        let mut rules = *self;
+        *rules.rule_mut(name).unwrap()
+    }
+
+    // Whether the equation computes and follows these rules
+    pub fn check(&self, eq: &Equation) -> Result<(), NerdleError> {
+        if !eq.computes()? {
+            return Err(NerdleError { message: format!("Equation '{}' does not compute", eq)});
+        }
+        let res = eq.res.int_value()?;
+        if res == 0 && !self.zero_results {
+            return Err(NerdleError { message: format!("Equation '{}' has a zero result", eq)});
+        }
+        if res < 0 && !self.negative_results {
+            return Err(NerdleError { message: format!("Equation '{}' has a negative result", eq)});
+        }
+        for part in eq.expr.parts.iter() {
+            match part {
+                ExpressionPart::Number(num) if !self.zero_operands && *num == ExpressionNumber::new(0) =>
+                    return Err(NerdleError { message: format!("Equation '{}' has a zero operand", eq)}),
+                _ => { }
+            }
+        }
+        if !self.negative_intermediates || !self.fractions {
+            self.check_steps(eq)?;
+        }
+        Ok(())
+    }
+
+    // Go through the calculation the way Expression::calculate does, multiplying and dividing each term before
+    // adding them up, checking every value along the way
+    fn check_steps(&self, eq: &Equation) -> Result<(), NerdleError> {
+        let parts = &eq.expr.parts;
+        let mut total: Option<ExpressionNumber> = None;
+        let mut term_op = None;
+        let mut i = 0;
+        while i < parts.len() {
+            let mut term = match &parts[i] {
+                ExpressionPart::Number(num) => num.clone(),
+                ExpressionPart::Operator(op) => return Err(NerdleError { message: format!("Equation '{}' has {} where a number should be", eq, op)}),
+            };
+            i += 1;
+            while let Some(ExpressionPart::Operator(op)) = parts.get(i) {
+                if op.precedence() != 0 {
+                    break;
+                }
+                if let Some(ExpressionPart::Number(num)) = parts.get(i + 1) {
+                    term = op.operate(&term, num)?;
+                    if !term.is_int() && !self.fractions {
+                        return Err(NerdleError { message: format!("Equation '{}' goes through the fraction {}", eq, term)});
+                    }
+                }
+                i += 2;
+            }
+            total = Some(match (total, &term_op) {
+                (Some(total), Some(ExpressionPart::Operator(op))) => op.operate(&total, &term)?,
+                _ => term,
+            });
+            if i < parts.len() && total.as_ref().map(|total| total.is_negative()).unwrap_or(false) && !self.negative_intermediates {
+                return Err(NerdleError { message: format!("Equation '{}' goes negative on the way", eq)});
+            }
+            term_op = parts.get(i).cloned();
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+// Rules as a comma-separated list of names, like "zero_operands,no_fractions".  A name turns that rule on and
+// "no_" in front turns it off; anything not mentioned is as in the default, which is also called "classic".
+impl FromStr for RuleSet {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut rules = RuleSet::default();
+        for name in input.split(',').map(|name| name.trim()).filter(|name| !name.is_empty() && *name != "classic") {
+            let (rule, on) = match name.strip_prefix("no_") {
+                Some(rule) => (rule, false),
+                None => (name, true),
+            };
+            *rules.rule_mut(rule)
+                .ok_or_else(|| NerdleError { message: format!("No rule named '{}', try one of {}", rule, RULE_NAMES.join(", "))})? = on;
+        }
+        Ok(rules)
+    }
+}
+
+// The rules which differ from the default, in the form FromStr reads
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let default = RuleSet::default();
+        let changed: Vec<String> = RULE_NAMES.iter()
+            .filter(|name| self.rule(name) != default.rule(name))
+            .map(|name| if self.rule(name) { name.to_string() } else { format!("no_{}", name) })
+            .collect();
+        if changed.is_empty() {
+            write!(f, "classic")
+        } else {
+            write!(f, "{}", changed.join(","))
+        }
+    }
+}
+
+#[test]
+fn rule_set_test() {
+    let classic = RuleSet::default();
+    let check = |rules: &RuleSet, eq: &str| rules.check(&Equation::from_str(eq).unwrap()).is_ok();
+    assert!(check(&classic, "12-12=0"));
+    assert!(check(&classic, "1-9+10=2"));
+    assert!(check(&classic, "3/2*4=6"));
+    assert!(check(&classic, "1/2+1/2=1"));
+    assert!(!check(&classic, "0+12=12"));
+    assert!(!check(&classic, "1-12=-11"));
+    assert!(!check(&classic, "1+2=4"));
+
+    let rules = RuleSet::from_str("zero_operands,negative_results,no_zero_results,no_negative_intermediates,no_fractions").unwrap();
+    assert!(check(&rules, "0+12=12"));
+    assert!(check(&rules, "1-12=-11"));
+    assert!(!check(&rules, "12-12=0"));
+    assert!(!check(&rules, "1-9+10=2"));
+    assert!(!check(&rules, "3/2*4=6"));
+    assert!(!check(&rules, "1/2+1/2=1"));
+    assert!(check(&rules, "2*3/2=3"));
+    assert_eq!(rules.to_string(), "zero_operands,no_zero_results,no_negative_intermediates,negative_results,no_fractions");
+    assert_eq!(RuleSet::from_str(&rules.to_string()).unwrap(), rules);
+    assert_eq!(classic.to_string(), "classic");
+    assert!(RuleSet::from_str("lenient").is_err());
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+
+use crate::eq::Equation;
+use crate::constraint::EquationConstraint;
+use crate::eqgen::{eqgen_all_constrained, eqgen_constrained};
+use crate::host::{NerdleHost, GameMode};
+use crate::nerdle::{NerdleResult, NerdleError, NERDLE_CHARACTERS, NERDLE_TURNS};
+use crate::rules::RuleSet;
+
+// A local referee which hosts Nerdle games over HTTP, so bots written in any language can play against it.
+//
+//   POST /games?mode=classic&seed=123          Start a game; mode and seed are optional, or give answer=...
+//   GET  /games/<id>                           Current state of a game
+//   POST /games/<id>/guess?guess=12%2B34%3D46  Make a guess and get the result back
+//
+// Everything comes back as JSON.  In query strings '+' is a plus sign, not a space.
+
+struct ServerGame {
+    mode: GameMode,
+    host: Box<dyn NerdleHost>,
+    guesses: Vec<(String, NerdleResult)>,
+    won: bool,
+}
+
+impl ServerGame {
+    fn over(&self) -> bool {
+        self.won || self.guesses.len() >= NERDLE_TURNS as usize
+    }
+
+    fn to_json(&self, id: u64) -> Value {
+        let mut state = json!({
+            "id": id,
+            "mode": self.mode.to_string(),
+            "characters": NERDLE_CHARACTERS,
+            "turns": NERDLE_TURNS,
+            "turn": self.guesses.len(),
+            "guesses": self.guesses.iter().map(|(guess, res)| json!({ "guess": guess, "result": res.to_string() })).collect::<Vec<Value>>(),
+            "won": self.won,
+            "over": self.over(),
+        });
+        // Only give away the answer once the game is over
+        if self.over() {
+            state["answer"] = json!(self.host.answer().map(|answer| answer.to_string()));
+        }
+        state
+    }
+}
+
+pub struct NerdleServer {
+    // Which equations count, for answers and guesses alike
+    rules: RuleSet,
+    answers: Vec<Equation>,
+    games: HashMap<u64, ServerGame>,
+    next_id: u64,
+}
+
+impl NerdleServer {
+    pub fn new(rules: RuleSet) -> NerdleServer {
+        NerdleServer {
+            rules,
+            answers: eqgen_all_constrained(&EquationConstraint { rules, ..Default::default() }),
+            games: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn serve(&mut self, port: u16) -> Result<(), NerdleError> {
+        let server = tiny_http::Server::http(("127.0.0.1", port))
+            .map_err(|err| NerdleError { message: format!("Could not listen on port {}: {}", port, err)})?;
+        println!("Serving Nerdle games on http://127.0.0.1:{}/games", port);
+
+        for request in server.incoming_requests() {
+            let (status, body) = self.handle(request.method().as_str(), request.url());
+            println!("{} {} => {}", request.method(), request.url(), status);
+            let response = tiny_http::Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
+            if let Err(err) = request.respond(response) {
+                println!("Error sending response: {}", err);
+            }
+        }
+        Ok(())
+    }
+
+    // Handle one request, returning the HTTP status and the JSON body
+    pub fn handle(&mut self, method: &str, url: &str) -> (u16, Value) {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let params = match parse_query(query) {
+            Ok(params) => params,
+            Err(err) => return (400, json!({ "error": err.message })),
+        };
+        let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+        let res = match (method, segments.as_slice()) {
+            ("POST", ["games"]) => self.new_game(&params),
+            ("GET", ["games", id]) => self.game(id).map(|(id, game)| match game {
+                Some(game) => (200, game.to_json(id)),
+                None => no_game(id),
+            }),
+            ("POST", ["games", id, "guess"]) => self.guess(id, &params),
+            _ => Ok((404, json!({ "error": format!("No such endpoint: {} {}", method, path) }))),
+        };
+        match res {
+            Ok(res) => res,
+            Err(err) => (400, json!({ "error": err.message })),
+        }
+    }
+
+    fn new_game(&mut self, params: &HashMap<String, String>) -> Result<(u16, Value), NerdleError> {
+        let mode = match params.get("mode") {
+            Some(mode) => GameMode::from_str(mode)?,
+            None => GameMode::Classic,
+        };
+
+        let answer = match (params.get("answer"), params.get("seed")) {
+            (Some(answer), _) => {
+                let answer = Equation::from_str(answer)
+                    .map_err(|err| NerdleError { message: format!("Invalid answer '{}': {}", answer, err)})?;
+                check_equation(&answer, &self.rules)?;
+                answer
+            },
+            (None, Some(seed)) => {
+                let seed = u64::from_str(seed)
+                    .map_err(|err| NerdleError { message: format!("Invalid seed '{}': {}", seed, err)})?;
+                let mut rng = StdRng::seed_from_u64(seed);
+                self.answers.choose(&mut rng)
+                    .ok_or_else(|| NerdleError { message: "No answers to choose from".to_string()})?
+                    .clone()
+            },
+            (None, None) => eqgen_constrained(&EquationConstraint { rules: self.rules, ..Default::default() })
+                .map_err(|err| NerdleError { message: format!("Failed to generate answer: {}", err)})?,
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let game = ServerGame {
+            mode,
+            host: mode.host(answer, &self.answers),
+            guesses: Vec::new(),
+            won: false,
+        };
+        let state = game.to_json(id);
+        self.games.insert(id, game);
+        Ok((201, state))
+    }
+
+    // The game with this id, or None if there isn't one.  An id which isn't a number at all is an error.
+    fn game(&self, id: &str) -> Result<(u64, Option<&ServerGame>), NerdleError> {
+        let id = u64::from_str(id)
+            .map_err(|err| NerdleError { message: format!("Invalid game id '{}': {}", id, err)})?;
+        Ok((id, self.games.get(&id)))
+    }
+
+    fn guess(&mut self, id: &str, params: &HashMap<String, String>) -> Result<(u16, Value), NerdleError> {
+        let (id, _) = self.game(id)?;
+        let game = match self.games.get_mut(&id) {
+            Some(game) => game,
+            None => return Ok(no_game(id)),
+        };
+        if game.over() {
+            return Ok((409, json!({ "error": format!("Game {} is already over", id) })));
+        }
+
+        let guess = params.get("guess")
+            .ok_or_else(|| NerdleError { message: "Missing guess parameter".to_string()})?;
+        let guess = Equation::from_str(guess)
+            .map_err(|err| NerdleError { message: format!("Invalid guess '{}': {}", guess, err)})?;
+        check_equation(&guess, &self.rules)?;
+
+        let res = game.host.respond(&guess)?;
+        game.won = res.won();
+        game.guesses.push((guess.to_string(), res.clone()));
+
+        let mut state = game.to_json(id);
+        state["result"] = json!(res.to_string());
+        Ok((200, state))
+    }
+}
+
+fn no_game(id: u64) -> (u16, Value) {
+    (404, json!({ "error": format!("No game with id {}", id) }))
+}
+
+// Only equations the real game would accept as a guess
+fn check_equation(eq: &Equation, rules: &RuleSet) -> Result<(), NerdleError> {
+    let len = eq.len()
+        .map_err(|err| NerdleError { message: format!("Equation '{}' has invalid length: {}", eq, err)})?;
+    if len != NERDLE_CHARACTERS as usize {
+        return Err(NerdleError { message: format!("Equation '{}' is wrong length ({} chars != {})", eq, len, NERDLE_CHARACTERS)});
+    }
+    rules.check(eq)
+}
+
+fn parse_query(query: &str) -> Result<HashMap<String, String>, NerdleError> {
+    let mut params = HashMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        params.insert(percent_decode(key)?, percent_decode(value)?);
+    }
+    Ok(params)
+}
+
+// Decode %XX escapes.  Unlike form encoding, '+' is left alone since it's common in equations.
+fn percent_decode(input: &str) -> Result<String, NerdleError> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)
+                .ok_or_else(|| NerdleError { message: format!("Truncated escape in '{}'", input)})?;
+            decoded.push(u8::from_str_radix(hex, 16)
+                .map_err(|err| NerdleError { message: format!("Invalid escape '%{}' in '{}': {}", hex, input, err)})?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|err| NerdleError { message: format!("Invalid UTF-8 in '{}': {}", input, err)})
+}
+
+#[cfg(test)]
+use crate::nerdle::nerdle_str;
+
+#[test]
+fn percent_decode_test() {
+    assert_eq!(percent_decode("12+34%3D46").unwrap(), "12+34=46");
+    assert_eq!(percent_decode("12%2B34=46").unwrap(), "12+34=46");
+    assert!(percent_decode("12%2").is_err());
+    assert!(percent_decode("12%zz").is_err());
+}
+
+#[test]
+fn server_game_test() {
+    let mut server = NerdleServer {
+        rules: RuleSet::default(),
+        answers: vec![Equation::from_str("12+34=46").unwrap()],
+        games: HashMap::new(),
+        next_id: 1,
+    };
+
+    let (status, state) = server.handle("POST", "/games?answer=12%2B34%3D46");
+    assert_eq!(status, 201);
+    assert_eq!(state["id"], 1);
+    assert!(state.get("answer").is_none());
+
+    // Invalid guesses don't use up a turn
+    let (status, _) = server.handle("POST", "/games/1/guess?guess=1%2B2%3D3");
+    assert_eq!(status, 400);
+    let (status, _) = server.handle("POST", "/games/1/guess?guess=10%2B20%3D31");
+    assert_eq!(status, 400);
+
+    let (status, state) = server.handle("POST", "/games/1/guess?guess=10+20=30");
+    assert_eq!(status, 200);
+    assert_eq!(state["result"], "G-GY-GY-");
+    assert_eq!(state["turn"], 1);
+
+    let (status, state) = server.handle("POST", "/games/1/guess?guess=12%2B34%3D46");
+    assert_eq!(status, 200);
+    assert_eq!(state["won"], true);
+    assert_eq!(state["answer"], "12+34=46");
+
+    let (status, _) = server.handle("POST", "/games/1/guess?guess=12%2B34%3D46");
+    assert_eq!(status, 409);
+
+    let (status, state) = server.handle("GET", "/games/1");
+    assert_eq!(status, 200);
+    assert_eq!(state["guesses"].as_array().unwrap().len(), 2);
+
+    let (status, _) = server.handle("GET", "/games/2");
+    assert_eq!(status, 404);
+    let (status, _) = server.handle("POST", "/games/2/guess?guess=12%2B34%3D46");
+    assert_eq!(status, 404);
+    let (status, _) = server.handle("GET", "/games/two");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn server_rules_test() {
+    let mut server = NerdleServer {
+        rules: RuleSet::from_str("zero_operands").unwrap(),
+        answers: vec![Equation::from_str("12*0+7=7").unwrap()],
+        games: HashMap::new(),
+        next_id: 1,
+    };
+    let (status, _) = server.handle("POST", "/games?answer=12*0%2B7%3D7");
+    assert_eq!(status, 201);
+    let (status, state) = server.handle("POST", "/games/1/guess?guess=0*14+9=9");
+    assert_eq!(status, 200);
+    assert_eq!(state["result"], nerdle_str("0*14+9=9", "12*0+7=7").unwrap().to_string());
+
+    // Classic rules don't allow zero operands
+    assert!(check_equation(&Equation::from_str("0*14+9=9").unwrap(), &RuleSet::default()).is_err());
+}
+
+#[test]
+fn server_seed_test() {
+    let mut server = NerdleServer {
+        rules: RuleSet::default(),
+        answers: ["12+34=46", "10+20=30", "43-21=22"].iter().map(|eq| Equation::from_str(eq).unwrap()).collect(),
+        games: HashMap::new(),
+        next_id: 1,
+    };
+
+    // The same seed always gives the same answer
+    let answer_for_seed = |server: &mut NerdleServer| {
+        let (_, state) = server.handle("POST", "/games?seed=42&mode=commutative");
+        let id = state["id"].as_u64().unwrap();
+        for _ in 0..NERDLE_TURNS {
+            server.handle("POST", &format!("/games/{}/guess?guess=43-21=22", id));
+        }
+        server.handle("GET", &format!("/games/{}", id)).1["answer"].clone()
+    };
+    assert_eq!(answer_for_seed(&mut server), answer_for_seed(&mut server));
+
+    let (status, _) = server.handle("POST", "/games?mode=nonsense");
+    assert_eq!(status, 400);
+}
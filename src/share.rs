@@ -0,0 +1,180 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::nerdle::{NerdleResult, NerdlePositionResult, NerdleError, NERDLE_CHARACTERS, NERDLE_TURNS};
+
+pub const SHARE_GAME_NAME: &str = "nerdlegame";
+
+const SHARE_GREEN: char = '\u{1F7E9}'; // Green square
+const SHARE_PURPLE: char = '\u{1F7EA}'; // Purple square, what Nerdle uses for "wrong place"
+const SHARE_YELLOW: char = '\u{1F7E8}'; // Yellow square, accepted for Wordle-style grids
+const SHARE_BLACK: char = '\u{2B1B}'; // Black square
+const SHARE_WHITE: char = '\u{2B1C}'; // White square, light-mode version of black
+
+// A game result in the format the official game uses when sharing, for example:
+//   nerdlegame 123 3/6
+//
+//   🟪⬛⬛🟪⬛🟩⬛⬛
+//   🟩🟪⬛🟩🟩🟩⬛🟪
+//   🟩🟩🟩🟩🟩🟩🟩🟩
+#[derive(Clone, Debug, PartialEq)]
+pub struct NerdleShare {
+    pub puzzle: Option<u32>,
+    pub rows: Vec<NerdleResult>,
+}
+
+impl NerdleShare {
+    pub fn new(puzzle: Option<u32>, rows: Vec<NerdleResult>) -> NerdleShare {
+        NerdleShare {
+            puzzle,
+            rows,
+        }
+    }
+
+    pub fn won(&self) -> bool {
+        self.rows.last().map(|res| res.won()).unwrap_or(false)
+    }
+
+    // Number of turns taken to win, or None if the game was lost
+    pub fn turns(&self) -> Option<usize> {
+        if self.won() {
+            Some(self.rows.len())
+        } else {
+            None
+        }
+    }
+}
+
+pub fn result_to_emoji(res: &NerdleResult) -> String {
+    res.positions.iter().map(|pos| match pos {
+        NerdlePositionResult::Green => SHARE_GREEN,
+        NerdlePositionResult::Yellow => SHARE_PURPLE,
+        NerdlePositionResult::Gray => SHARE_BLACK,
+    }).collect()
+}
+
+pub fn result_from_emoji(row: &str) -> Result<NerdleResult, NerdleError> {
+    let mut res = NerdleResult::default();
+    let squares: Vec<char> = row.chars().filter(|ch| !ch.is_whitespace()).collect();
+    if squares.len() != NERDLE_CHARACTERS as usize {
+        return Err(NerdleError { message: format!("Share row '{}' had {} squares instead of {}", row, squares.len(), NERDLE_CHARACTERS)});
+    }
+    for (i, ch) in squares.iter().enumerate() {
+        res.positions[i] = match *ch {
+            SHARE_GREEN => NerdlePositionResult::Green,
+            SHARE_PURPLE | SHARE_YELLOW => NerdlePositionResult::Yellow,
+            SHARE_BLACK | SHARE_WHITE => NerdlePositionResult::Gray,
+            _ => return Err(NerdleError { message: format!("Share row '{}' had invalid square '{}'", row, ch)})
+        }
+    }
+    Ok(res)
+}
+
+fn is_emoji_row(line: &str) -> bool {
+    let mut chars = line.chars().filter(|ch| !ch.is_whitespace()).peekable();
+    chars.peek().is_some() && chars.all(|ch| matches!(ch, SHARE_GREEN | SHARE_PURPLE | SHARE_YELLOW | SHARE_BLACK | SHARE_WHITE))
+}
+
+impl FromStr for NerdleShare {
+    type Err = NerdleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut lines = input.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+
+        let header = lines.next().ok_or_else(|| NerdleError { message: "Share text was empty".to_string() })?;
+        let words: Vec<&str> = header.split_whitespace().collect();
+        let (puzzle, score) = match words.as_slice() {
+            [SHARE_GAME_NAME, puzzle, score] => {
+                let puzzle = u32::from_str(puzzle).map_err(|err| NerdleError { message: format!("Invalid puzzle number '{}' in '{}': {}", puzzle, header, err)})?;
+                (Some(puzzle), *score)
+            },
+            [SHARE_GAME_NAME, score] => (None, *score),
+            _ => return Err(NerdleError { message: format!("Share text header '{}' should look like '{} 123 3/{}'", header, SHARE_GAME_NAME, NERDLE_TURNS)})
+        };
+
+        let (turns, max_turns) = score.split_once('/')
+            .ok_or_else(|| NerdleError { message: format!("Invalid score '{}' in '{}'", score, header)})?;
+        let max_turns = u32::from_str(max_turns).map_err(|err| NerdleError { message: format!("Invalid maximum turns '{}' in '{}': {}", max_turns, header, err)})?;
+        let turns = match turns {
+            "X" | "x" => None,
+            turns => Some(usize::from_str(turns).map_err(|err| NerdleError { message: format!("Invalid turn count '{}' in '{}': {}", turns, header, err)})?),
+        };
+
+        // Anything after the grid (like a link to the game) is ignored
+        let rows = lines
+            .take_while(|line| is_emoji_row(line))
+            .map(result_from_emoji)
+            .collect::<Result<Vec<NerdleResult>, NerdleError>>()?;
+        if rows.len() > max_turns as usize {
+            return Err(NerdleError { message: format!("Share text had {} rows but only {} turns are allowed", rows.len(), max_turns)});
+        }
+
+        let share = NerdleShare::new(puzzle, rows);
+        if let Some(pos) = share.rows.iter().position(|res| res.won()) {
+            if pos + 1 != share.rows.len() {
+                return Err(NerdleError { message: format!("Share text continued after a winning row on turn {}", pos + 1)});
+            }
+        }
+        match turns {
+            Some(turns) if share.turns() != Some(turns) => Err(NerdleError { message: format!("Share text claims a win in {} turns but has {} rows ending in {}", turns, share.rows.len(), if share.won() { "a win" } else { "a loss" })}),
+            None if share.won() => Err(NerdleError { message: "Share text claims a loss but ends with a winning row".to_string()}),
+            _ => Ok(share),
+        }
+    }
+}
+
+impl fmt::Display for NerdleShare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", SHARE_GAME_NAME)?;
+        if let Some(puzzle) = self.puzzle {
+            write!(f, " {}", puzzle)?;
+        }
+        match self.turns() {
+            Some(turns) => writeln!(f, " {}/{}", turns, NERDLE_TURNS)?,
+            None => writeln!(f, " X/{}", NERDLE_TURNS)?,
+        }
+        writeln!(f)?;
+        for res in self.rows.iter() {
+            writeln!(f, "{}", result_to_emoji(res))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn share_round_trip_test() {
+    let text = "nerdlegame 123 3/6\n\n\u{1F7EA}\u{2B1B}\u{2B1B}\u{1F7EA}\u{2B1B}\u{1F7E9}\u{2B1B}\u{2B1B}\n\u{1F7E9}\u{1F7EA}\u{2B1B}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{2B1B}\u{1F7EA}\n\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\n";
+    let share = NerdleShare::from_str(text).unwrap();
+    assert_eq!(share.puzzle, Some(123));
+    assert_eq!(share.turns(), Some(3));
+    assert_eq!(share.rows[0].to_string(), "Y--Y-G--");
+    assert_eq!(share.rows[1].to_string(), "GY-GGG-Y");
+    assert!(share.rows[2].won());
+    assert_eq!(share.to_string(), text);
+}
+
+#[test]
+fn share_loss_test() {
+    let rows = vec![NerdleResult::from_str("Y--Y-G--").unwrap(); NERDLE_TURNS as usize];
+    let share = NerdleShare::new(None, rows);
+    assert!(!share.won());
+    let text = share.to_string();
+    assert!(text.starts_with("nerdlegame X/6\n"));
+
+    let parsed = NerdleShare::from_str(&text).unwrap();
+    assert_eq!(parsed.puzzle, None);
+    assert_eq!(parsed.rows.len(), NERDLE_TURNS as usize);
+    assert_eq!(parsed.turns(), None);
+}
+
+#[test]
+fn share_invalid_test() {
+    // Wrong header
+    assert!(NerdleShare::from_str("wordle 123 3/6\n").is_err());
+    // Turn count does not match rows
+    assert!(NerdleShare::from_str("nerdlegame 123 2/6\n\n\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\n").is_err());
+    // Claims a loss but won
+    assert!(NerdleShare::from_str("nerdlegame 123 X/6\n\n\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\u{1F7E9}\n").is_err());
+    // Short row
+    assert!(NerdleShare::from_str("nerdlegame 123 1/6\n\n\u{1F7E9}\u{1F7E9}\n").is_err());
+}
@@ -4,6 +4,9 @@ use crate::eq::Equation;
 use crate::constraint::{NoMatchFound};
 use crate::nerdle::{NerdleResult, NerdleError};
 use crate::nerdsolver::NerdleSolver;
+use crate::external::ExternalStrategy;
+use crate::explain::ImpossibleReason;
+use crate::rules::RuleSet;
 
 
 pub trait Strategy {
@@ -11,14 +14,29 @@ pub trait Strategy {
     fn update(&mut self, guess: &Equation, result: &NerdleResult);
     fn print_hint(&self);
     fn answer_ok(&self, eq: &Equation) -> Result<(), NerdleError>;
+    // Every reason the equation can't be the answer, tied to the turn that showed it; empty if it's possible
+    fn explain(&self, eq: &Equation) -> Vec<ImpossibleReason>;
+    // Play by the rule that commuted forms of the answer also win
+    fn set_commutative(&mut self, commutative: bool);
+    // Which equations count, for the guesses and the answer
+    fn set_rules(&mut self, rules: RuleSet);
+    // Everything the strategy knows as JSON, to attach to a bug report; None if it can't share that
+    fn state(&self) -> Option<String>;
 }
 
 pub enum StrategyEnum {
     FirstPossible(NerdleSolver),
+    External(ExternalStrategy),
 }
 
 impl StrategyEnum {
+    // "external:<command>" runs a program speaking the bot protocol as the strategy
     pub fn by_name(name: &str) -> Result<StrategyEnum, NoSuchStrategyError> {
+        if let Some(command) = name.strip_prefix("external:") {
+            return ExternalStrategy::new(command)
+                .map(StrategyEnum::External)
+                .map_err(|err| NoSuchStrategyError { message: format!("Could not start strategy '{}': {}", name, err)});
+        }
         match name {
             "first_possible" => Ok(StrategyEnum::FirstPossible(NerdleSolver::new())),
             _ => Err(NoSuchStrategyError { message: format!("No strategy named '{}'", name)})
@@ -28,12 +46,14 @@ impl StrategyEnum {
     pub fn as_strategy(&self) -> &dyn Strategy {
         match self {
             StrategyEnum::FirstPossible(solver) => solver,
+            StrategyEnum::External(strategy) => strategy,
         }
     }
 
     pub fn as_strategy_mut(&mut self) -> &mut dyn Strategy {
         match self {
             StrategyEnum::FirstPossible(solver) => solver,
+            StrategyEnum::External(strategy) => strategy,
         }
     }
 }
@@ -54,12 +74,29 @@ impl Strategy for StrategyEnum {
     fn answer_ok(&self, guess: &Equation) -> Result<(), NerdleError> {
         self.as_strategy().answer_ok(guess)
     }
+
+    fn explain(&self, eq: &Equation) -> Vec<ImpossibleReason> {
+        self.as_strategy().explain(eq)
+    }
+
+    fn set_commutative(&mut self, commutative: bool) {
+        self.as_strategy_mut().set_commutative(commutative)
+    }
+
+    fn set_rules(&mut self, rules: RuleSet) {
+        self.as_strategy_mut().set_rules(rules)
+    }
+
+    fn state(&self) -> Option<String> {
+        self.as_strategy().state()
+    }
 }
 
 impl fmt::Display for StrategyEnum {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             StrategyEnum::FirstPossible(solver) => solver.fmt(f),
+            StrategyEnum::External(strategy) => strategy.fmt(f),
         }
     }
 }
@@ -1,36 +1,14 @@
-use std::ops::RangeInclusive;
-use rand::Rng;
-use rand::distributions::uniform::SampleUniform;
-
-use crate::constraint::NoMatchFound;
-
-pub fn range_rand_or_only<T>(range: RangeInclusive<T>) -> Result<T, NoMatchFound>
-where
-    T: PartialOrd,
-    T: std::fmt::Display,
-    T: SampleUniform,
-    T: Copy,
-{
-    if range.is_empty() {
-        if range.start() == range.end() {
-            Ok(*(range.start()))
-        } else {
-            Err(NoMatchFound { message: format!("Could not choose item from range {}..={}", range.start(), range.end())})
-        }
-    } else {
-        let mut rng = rand::thread_rng();
-        Ok(rng.gen_range(range))
-    }
-}
-
 // From https://stackoverflow.com/questions/1489830/efficient-way-to-determine-number-of-digits-in-an-integer
-pub fn num_digits(x: i32) -> u32 {
+pub fn num_digits(x: i64) -> u32 {
     // TODO: What about int_min?
     // if (x == INT32_MIN) return 10 + 1;
     if x < 0 {
         return num_digits(-x) + 1;
     }
 
+    if x >= 10000000000 {
+        return num_digits(x / 10000000000) + 10;
+    }
     if x >= 10000 {
         if x >= 10000000 {
             if x >= 100000000 {
@@ -61,16 +39,6 @@ pub fn num_digits(x: i32) -> u32 {
     return 1;
 }
 
-#[test]
-fn range_rand_or_only_test() {
-    assert_eq!(3, range_rand_or_only::<u32>(3..=3).unwrap());
-    for _i in 0..100 {
-        let x = range_rand_or_only::<u32>(1..=10).unwrap();
-        assert!((1..=10).contains(&x));
-    }
-    assert!(!range_rand_or_only::<u32>(3..=2).is_ok());
-}
-
 #[test]
 fn num_digits_test() {
     for i in 0..=9 {
@@ -85,4 +53,8 @@ fn num_digits_test() {
     for i in 1000..=9999 {
         assert_eq!(4, num_digits(i));
     }
+    assert_eq!(10, num_digits(9999999999));
+    assert_eq!(11, num_digits(10000000000));
+    assert_eq!(19, num_digits(i64::MAX));
+    assert_eq!(13, num_digits(-123456789012));
 }